@@ -0,0 +1,192 @@
+/*
+ Attack tables
+ Garbage lines a lock would send to an opponent.
+*/
+use super::{components::SpinType, scoring::LockEvent};
+
+#[derive(Clone)]
+pub struct AttackTable {
+    pub name: &'static str,
+    // indexed by cleared lines
+    pub lines: [usize; 5],
+    pub t_spin: [usize; 4],
+    pub t_spin_mini: [usize; 3],
+    pub b2b_bonus: usize,
+    // indexed by combo count, the last entry repeats
+    pub combo: Vec<usize>,
+    pub perfect_clear: usize,
+}
+
+impl AttackTable {
+    // Puyo Puyo Tetris and most modern guideline games
+    pub fn guideline() -> AttackTable {
+        AttackTable {
+            name: "GUIDELINE",
+            lines: [0, 0, 1, 2, 4],
+            t_spin: [0, 2, 4, 6],
+            t_spin_mini: [0, 0, 1],
+            b2b_bonus: 1,
+            combo: vec![0, 0, 1, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5],
+            perfect_clear: 10,
+        }
+    }
+
+    pub fn tetris_99() -> AttackTable {
+        AttackTable {
+            name: "TETRIS 99",
+            lines: [0, 0, 1, 2, 4],
+            t_spin: [0, 2, 4, 6],
+            t_spin_mini: [0, 0, 1],
+            b2b_bonus: 1,
+            combo: vec![0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5],
+            perfect_clear: 10,
+        }
+    }
+
+    // versus modes before the guideline: no spins, combos or back-to-back
+    pub fn classic() -> AttackTable {
+        AttackTable {
+            name: "CLASSIC",
+            lines: [0, 0, 1, 2, 4],
+            t_spin: [0, 0, 1, 2],
+            t_spin_mini: [0, 0, 1],
+            b2b_bonus: 0,
+            combo: vec![0],
+            perfect_clear: 0,
+        }
+    }
+
+    pub fn presets() -> Vec<AttackTable> {
+        vec![
+            AttackTable::guideline(),
+            AttackTable::tetris_99(),
+            AttackTable::classic(),
+        ]
+    }
+}
+
+pub fn lines_sent(table: &AttackTable, event: &LockEvent) -> usize {
+    if event.lines == 0 {
+        return 0;
+    }
+
+    let lines = event.lines.min(4);
+    let mut sent = match event.spin {
+        SpinType::None => table.lines[lines],
+        SpinType::Mini => table.t_spin_mini[lines.min(2)],
+        SpinType::Full => table.t_spin[lines.min(3)],
+    };
+    if event.b2b {
+        sent += table.b2b_bonus;
+    }
+    sent += match table.combo.get(event.combo) {
+        Some(bonus) => *bonus,
+        None => *table.combo.last().unwrap_or(&0),
+    };
+    if event.perfect_clear {
+        sent += table.perfect_clear;
+    }
+
+    sent
+}
+
+// attack per minute, timers run at 60 frames per second
+pub fn attack_per_minute(attack: usize, frames: usize) -> f32 {
+    if frames == 0 {
+        return 0.0;
+    }
+
+    attack as f32 * 3600.0 / frames as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(lines: usize, spin: SpinType, combo: usize, b2b: bool) -> LockEvent {
+        LockEvent {
+            lines,
+            spin,
+            combo,
+            b2b,
+            perfect_clear: false,
+            soft_drop: 0,
+            hard_drop: 0,
+            level: 0,
+        }
+    }
+
+    #[test]
+    fn no_lines_sends_nothing() {
+        for table in AttackTable::presets() {
+            let mut lock = event(0, SpinType::Full, 5, true);
+            lock.perfect_clear = true;
+            assert_eq!(lines_sent(&table, &lock), 0, "{}", table.name);
+        }
+    }
+
+    #[test]
+    fn guideline_clears() {
+        let table = AttackTable::guideline();
+        assert_eq!(lines_sent(&table, &event(1, SpinType::None, 0, false)), 0);
+        assert_eq!(lines_sent(&table, &event(2, SpinType::None, 0, false)), 1);
+        assert_eq!(lines_sent(&table, &event(3, SpinType::None, 0, false)), 2);
+        assert_eq!(lines_sent(&table, &event(4, SpinType::None, 0, false)), 4);
+        assert_eq!(lines_sent(&table, &event(4, SpinType::None, 0, true)), 5);
+        assert_eq!(lines_sent(&table, &event(2, SpinType::Full, 0, false)), 4);
+        assert_eq!(lines_sent(&table, &event(3, SpinType::Full, 0, true)), 7);
+        assert_eq!(lines_sent(&table, &event(2, SpinType::Mini, 0, false)), 1);
+    }
+
+    #[test]
+    fn big_pieces_count_as_a_tetris() {
+        let table = AttackTable::guideline();
+        assert_eq!(lines_sent(&table, &event(8, SpinType::None, 0, false)), 4);
+        assert_eq!(lines_sent(&table, &event(4, SpinType::Full, 0, false)), 6);
+        assert_eq!(lines_sent(&table, &event(3, SpinType::Mini, 0, false)), 1);
+    }
+
+    #[test]
+    fn combo_bonus_repeats_last_entry() {
+        let guideline = AttackTable::guideline();
+        assert_eq!(
+            lines_sent(&guideline, &event(1, SpinType::None, 2, false)),
+            1
+        );
+        assert_eq!(
+            lines_sent(&guideline, &event(1, SpinType::None, 12, false)),
+            5
+        );
+        assert_eq!(
+            lines_sent(&guideline, &event(1, SpinType::None, 30, false)),
+            5
+        );
+
+        let tetris_99 = AttackTable::tetris_99();
+        assert_eq!(
+            lines_sent(&tetris_99, &event(1, SpinType::None, 1, false)),
+            1
+        );
+        assert_eq!(
+            lines_sent(&tetris_99, &event(1, SpinType::None, 20, false)),
+            5
+        );
+    }
+
+    #[test]
+    fn classic_has_no_bonuses() {
+        let table = AttackTable::classic();
+        let mut lock = event(4, SpinType::None, 8, true);
+        lock.perfect_clear = true;
+        assert_eq!(lines_sent(&table, &lock), 4);
+        assert_eq!(lines_sent(&table, &event(2, SpinType::Full, 0, false)), 1);
+    }
+
+    #[test]
+    fn perfect_clear_adds_to_the_clear() {
+        let table = AttackTable::guideline();
+        let mut lock = event(4, SpinType::None, 0, false);
+        lock.perfect_clear = true;
+        assert_eq!(lines_sent(&table, &lock), 14);
+    }
+}
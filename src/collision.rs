@@ -146,6 +146,28 @@ pub fn completed_lines(placed: &Vec<Option<Block>>) -> Vec<usize> {
     completed
 }
 
+pub fn is_perfect_clear(placed: &[Option<Block>], completed: &[usize]) -> bool {
+    placed
+        .iter()
+        .enumerate()
+        .all(|(idx, block)| block.is_none() || completed.contains(&(idx / WELL_WIDTH)))
+}
+
+// an empty cell below a block in the same column, rows about to be cleared are skipped
+pub fn has_holes(placed: &[Option<Block>], completed: &[usize]) -> bool {
+    (0..WELL_WIDTH).any(|x| {
+        let mut covered = false;
+        (0..WELL_HEIGHT)
+            .filter(|y| !completed.contains(y))
+            .any(|y| {
+                let filled = placed[y * WELL_WIDTH + x].is_some();
+                let hole = covered && !filled;
+                covered |= filled;
+                hole
+            })
+    })
+}
+
 pub fn surrounded_by_neighbours(placed: &Vec<Option<Block>>, x: usize, y: usize) -> bool {
     let mut neighbours = 0;
     if x > 0 {
@@ -1,6 +1,16 @@
-use super::spawner;
+use super::{
+    attack::AttackTable,
+    config::Config,
+    controls::{Action, Bindings},
+    custom::CustomMode,
+    demo::Bot,
+    modes::{self, B_TYPE_HEIGHTS, DIG_ROWS, MARATHON_GOALS, SPRINT_LINES, ULTRA_MINUTES},
+    puzzle::Puzzle,
+    scoring::{ScoringKind, ScoringSystem},
+    spawner,
+};
 use macroquad::{
-    prelude::{color_u8, vec2, Color, KeyCode, Mat3, Mat4, Texture2D, Vec2},
+    prelude::{color_u8, vec2, Color, Mat3, Mat4, Texture2D, Vec2},
     text::Font,
 };
 use std::collections::HashMap;
@@ -15,6 +25,99 @@ pub enum GameMode {
     HighScore,
     Ending,
     Demo,
+    Controls,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameType {
+    Marathon,
+    BType,
+    Sprint,
+    Ultra,
+    Dig,
+    Survival,
+    Master,
+    Zen,
+    Puzzle,
+    Big,
+    // index into the loaded custom modes
+    Custom(usize),
+}
+
+impl GameType {
+    pub fn all() -> Vec<GameType> {
+        vec![
+            GameType::Marathon,
+            GameType::BType,
+            GameType::Sprint,
+            GameType::Ultra,
+            GameType::Dig,
+            GameType::Survival,
+            GameType::Master,
+            GameType::Zen,
+            GameType::Puzzle,
+            GameType::Big,
+        ]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameType::Marathon => "MARATHON",
+            GameType::BType => "B-TYPE",
+            GameType::Sprint => "SPRINT",
+            GameType::Ultra => "ULTRA",
+            GameType::Dig => "DIG",
+            GameType::Survival => "SURVIVAL",
+            GameType::Master => "MASTER",
+            GameType::Zen => "ZEN",
+            GameType::Puzzle => "PUZZLE",
+            GameType::Big => "BIG",
+            GameType::Custom(_) => "CUSTOM",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            GameType::Marathon => "REACH THE GOAL BEFORE YOU TOP OUT",
+            GameType::BType => "CLEAR 25 LINES FROM A GARBAGE START",
+            GameType::Sprint => "CLEAR THE LINES AS FAST AS POSSIBLE",
+            GameType::Ultra => "SCORE AS MUCH AS POSSIBLE IN TIME",
+            GameType::Dig => "DIG THROUGH THE GARBAGE AS FAST AS POSSIBLE",
+            GameType::Survival => "HOLD OUT AGAINST RISING GARBAGE",
+            GameType::Master => "REACH LEVEL 999 AT 20G FOR THE BEST GRADE",
+            GameType::Zen => "RELAX, THE STACK NEVER TOPS OUT",
+            GameType::Puzzle => "MEET THE MISSION GOAL WITH THE PIECES GIVEN",
+            GameType::Big => "DOUBLE SIZE PIECES IN A HALF SIZE WELL",
+            GameType::Custom(_) => "A MODE READ FROM THE MODES FILE",
+        }
+    }
+
+    pub fn has_level(self) -> bool {
+        match self {
+            GameType::Marathon | GameType::BType | GameType::Big => true,
+            GameType::Sprint
+            | GameType::Ultra
+            | GameType::Dig
+            | GameType::Survival
+            | GameType::Master
+            | GameType::Zen
+            | GameType::Puzzle
+            | GameType::Custom(_) => false,
+        }
+    }
+
+    pub fn levels_up(self) -> bool {
+        match self {
+            GameType::Marathon | GameType::Ultra | GameType::Big | GameType::Custom(_) => true,
+            GameType::BType
+            | GameType::Sprint
+            | GameType::Dig
+            | GameType::Survival
+            | GameType::Master
+            | GameType::Zen
+            | GameType::Puzzle => false,
+        }
+    }
 }
 
 //colors
@@ -34,15 +137,36 @@ pub const WELL_CELL_GAP: f32 = 1.0 / PIXELS_PER_UNIT as f32;
 pub const WELL_CELL: f32 = 1.0;
 
 // timers in frames
-pub const AUTO_SHIFT_TIMEOUT: f64 = 0.05;
-pub const AUTO_SHIFT_DELAY: f64 = 0.3;
+pub const DEFAULT_DAS: usize = 18;
+pub const DEFAULT_ARR: usize = 3;
+pub const MAX_DAS: usize = 30;
+pub const MAX_ARR: usize = 10;
+pub const MAX_DAS_CUT: usize = 10;
 pub const HARD_DROP_GRAVITY: f32 = 1.0;
-pub const SOFT_DROP_GRAVITY: f32 = 5.0;
 pub const LOCK_DELAY: usize = 30;
 pub const ENTRY_DELAY: usize = 20;
 pub const LINE_CLEAR_DELAY: usize = 20;
 pub const SCORE_TIMEOUT: usize = 60;
 
+// entry, lock and line clear timers, shortened by master mode
+#[derive(Clone, Copy)]
+pub struct Delays {
+    pub entry: usize,
+    pub lock: usize,
+    pub line_clear: usize,
+}
+
+impl Default for Delays {
+    fn default() -> Self {
+        Delays {
+            entry: ENTRY_DELAY,
+            lock: LOCK_DELAY,
+            line_clear: LINE_CLEAR_DELAY,
+        }
+    }
+}
+pub const CALLOUT_TIMEOUT: usize = 90;
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub enum TetrominoType {
     J,
@@ -70,21 +194,28 @@ pub struct Tetromino {
     pub locking: bool,
     pub sonic_lock: bool,
     pub held: bool,
+    pub soft_dropped: usize,
+    pub hard_dropped: usize,
+    // moves and rotations pressed, for finesse
+    pub inputs: usize,
+    // well cells per side of each block, pos counts in blocks of this size
+    pub scale: usize,
 }
 
 impl Tetromino {
     pub fn relative_points(self: &Self, pos: &Vec2) -> Vec<Vec2> {
-        let mut points = Vec::new();
+        let mut cells = Vec::new();
         let x = pos.x;
         let y = pos.y;
+        let height = (WELL_HEIGHT / self.scale) as f32;
         match self.kind {
             TetrominoType::I | TetrominoType::O => {
                 for r in 0..4 {
                     for c in 0..4 {
                         if self.mat4.row(r)[c] == 1.0 {
                             let dx = x + r as f32;
-                            let dy = WELL_HEIGHT as f32 - (y + c as f32);
-                            points.push(vec2(dx, dy))
+                            let dy = height - (y + c as f32);
+                            cells.push(vec2(dx, dy))
                         }
                     }
                 }
@@ -94,20 +225,30 @@ impl Tetromino {
                     for c in 0..3 {
                         if self.mat.row(r)[c] == 1.0 {
                             let dx = x + r as f32;
-                            let dy = WELL_HEIGHT as f32 - (y + c as f32);
-                            points.push(vec2(dx, dy))
+                            let dy = height - (y + c as f32);
+                            cells.push(vec2(dx, dy))
                         }
                     }
                 }
             }
         }
 
+        // every cell of a big piece covers scale x scale cells of the well
+        let scale = self.scale as f32;
+        let mut points = Vec::new();
+        for cell in cells {
+            for i in 0..self.scale {
+                for j in 0..self.scale {
+                    points.push(vec2(cell.x * scale + i as f32, cell.y * scale + j as f32));
+                }
+            }
+        }
+
         points
     }
 }
 
 pub struct LineClear {
-    pub y_pos: usize,
     pub lines: Vec<usize>,
     pub counter: usize,
 }
@@ -121,6 +262,11 @@ pub struct Ghost {
 pub struct Block {
     pub color: Color,
     pub kind: TetrominoType,
+    pub garbage: bool,
+    // frame the block was placed on
+    pub locked: usize,
+    // the piece it was part of, garbage and puzzle boards use 0
+    pub piece: usize,
 }
 
 pub struct Score {
@@ -128,16 +274,49 @@ pub struct Score {
     pub lines: usize,
     pub val: usize,
     pub topout: bool,
+    // consecutive line clearing locks
+    pub combo: usize,
+    // last clear was a tetris or a spin
+    pub b2b: bool,
+    // garbage lines the clears would have sent
+    pub attack: usize,
+    pub frames: usize,
+    // awarded when a goal is reached
+    pub bonus: usize,
+    pub finesse_faults: usize,
+    // the stored record before this game
+    pub record: Option<usize>,
+    // place in the high score table, counted from 0
+    pub rank: Option<usize>,
+    // the puzzle objective was met
+    pub solved: bool,
+    // cascade clears following the last lock
+    pub chain: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SpinType {
+    None,
+    Mini,
+    Full,
 }
 
 pub struct KeyInfo {
-    pub auto_shift_start: f64,
-    pub auto_shift: (Option<KeyCode>, f64),
+    // direction being held and the frames it has been held for
+    pub auto_shift: Option<Action>,
+    pub das_timer: usize,
+    // frames until the next repeat once charged
+    pub arr_timer: usize,
+    // spawn count when the current piece entered the well
+    pub entered: Option<usize>,
+    // frames left without repeats after the piece entered
+    pub das_cut: usize,
 }
 
 pub struct Gravity {
     pub meter: f32,
     pub max: f32,
+    pub soft_drop: bool,
 }
 
 pub struct ScorePopup {
@@ -145,6 +324,11 @@ pub struct ScorePopup {
     pub creation: usize,
 }
 
+pub struct Callout {
+    pub text: String,
+    pub creation: usize,
+}
+
 pub enum FrameInput {
     None,
     Move,
@@ -168,8 +352,504 @@ pub struct GameState {
     pub font: Font,
     pub statistics: HashMap<TetrominoType, usize>,
     pub last_score: ScorePopup,
+    pub callouts: Vec<Callout>,
     pub line_clear: Option<LineClear>,
     pub game_mode: GameMode,
+    pub scoring: Box<dyn ScoringSystem>,
+    pub options: Options,
+    pub menu_cursor: usize,
+    pub high_scores: Vec<usize>,
+    // the goal was reached and play continues without one
+    pub endless: bool,
+    // frames since garbage last rose and until it rises next
+    pub garbage_timer: usize,
+    pub garbage_interval: usize,
+    pub delays: Delays,
+    // frames at which each hundred levels of master mode was reached
+    pub section_splits: Vec<usize>,
+    pub puzzles: Vec<Puzzle>,
+    pub custom_modes: Vec<CustomMode>,
+    // options as the settings file left them, before options.sav
+    pub settings: Options,
+    pub randomizer: Randomizer,
+    // pieces left in the current bag
+    pub bag: Vec<Tetromino>,
+    // pieces brought into play, held ones included
+    pub spawns: usize,
+    // frames the title screen has waited for a key
+    pub idle: usize,
+    // the controls screen waits for a key to bind
+    pub binding: bool,
+    pub demo: Bot,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LevelRule {
+    // first transition after min(start * 10 + 10, max(100, start * 10 - 50)) lines
+    Nes,
+    // every 10 lines
+    Guideline,
+}
+
+impl LevelRule {
+    pub fn name(self) -> &'static str {
+        match self {
+            LevelRule::Nes => "NES",
+            LevelRule::Guideline => "GUIDELINE",
+        }
+    }
+}
+
+// how long placed blocks stay on screen
+#[derive(Clone, Copy, PartialEq)]
+pub enum StackVisibility {
+    Visible,
+    Fading,
+    Invisible,
+}
+
+impl StackVisibility {
+    pub const ALL: [StackVisibility; 3] = [
+        StackVisibility::Visible,
+        StackVisibility::Fading,
+        StackVisibility::Invisible,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StackVisibility::Visible => "VISIBLE",
+            StackVisibility::Fading => "FADING",
+            StackVisibility::Invisible => "INVISIBLE",
+        }
+    }
+
+    pub fn next(self, forward: bool) -> StackVisibility {
+        match (self, forward) {
+            (StackVisibility::Visible, true) | (StackVisibility::Invisible, false) => {
+                StackVisibility::Fading
+            }
+            (StackVisibility::Fading, true) | (StackVisibility::Visible, false) => {
+                StackVisibility::Invisible
+            }
+            (StackVisibility::Invisible, true) | (StackVisibility::Fading, false) => {
+                StackVisibility::Visible
+            }
+        }
+    }
+}
+
+// how the next pieces are picked
+#[derive(Clone, Copy, PartialEq)]
+pub enum Randomizer {
+    Random,
+    // all seven pieces in a shuffled order, then again
+    Bag,
+}
+
+// what happens to the stack above cleared lines
+#[derive(Clone, Copy, PartialEq)]
+pub enum LineGravity {
+    // every row above drops by the number of cleared lines
+    Naive,
+    // touching blocks fall together until they land
+    Sticky,
+    // each piece falls on its own and new full lines clear as a chain
+    Cascade,
+}
+
+impl LineGravity {
+    pub const ALL: [LineGravity; 3] = [
+        LineGravity::Naive,
+        LineGravity::Sticky,
+        LineGravity::Cascade,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LineGravity::Naive => "NAIVE",
+            LineGravity::Sticky => "STICKY",
+            LineGravity::Cascade => "CASCADE",
+        }
+    }
+
+    pub fn next(self, forward: bool) -> LineGravity {
+        match (self, forward) {
+            (LineGravity::Naive, true) | (LineGravity::Cascade, false) => LineGravity::Sticky,
+            (LineGravity::Sticky, true) | (LineGravity::Naive, false) => LineGravity::Cascade,
+            (LineGravity::Cascade, true) | (LineGravity::Sticky, false) => LineGravity::Naive,
+        }
+    }
+}
+
+// how a held soft drop falls, as a multiple of the current gravity
+#[derive(Clone, Copy, PartialEq)]
+pub enum SoftDrop {
+    Factor(usize),
+    Instant,
+}
+
+impl SoftDrop {
+    // steps on the controls screen, instant comes after the last
+    const FACTORS: [usize; 6] = [2, 5, 10, 20, 40, 80];
+
+    pub fn name(self) -> String {
+        match self {
+            SoftDrop::Factor(factor) => format!("{}X", factor),
+            SoftDrop::Instant => "INSTANT".to_string(),
+        }
+    }
+
+    fn parse(text: &str) -> Option<SoftDrop> {
+        if text.eq_ignore_ascii_case("instant") {
+            return Some(SoftDrop::Instant);
+        }
+        match text.trim_end_matches(['x', 'X']).parse() {
+            Ok(factor) if factor >= 1 => Some(SoftDrop::Factor(factor)),
+            _ => None,
+        }
+    }
+
+    pub fn next(self, forward: bool) -> SoftDrop {
+        let steps = SoftDrop::FACTORS.len();
+        let step = match self {
+            SoftDrop::Factor(factor) => SoftDrop::FACTORS
+                .iter()
+                .position(|f| *f >= factor)
+                .unwrap_or(steps),
+            SoftDrop::Instant => steps,
+        };
+        let step = if forward {
+            usize::min(step + 1, steps)
+        } else {
+            step.saturating_sub(1)
+        };
+        match SoftDrop::FACTORS.get(step) {
+            Some(factor) => SoftDrop::Factor(*factor),
+            None => SoftDrop::Instant,
+        }
+    }
+
+    // never slower than gravity, instant is 20G
+    pub fn frames_per_row(self, gravity: f32) -> f32 {
+        match self {
+            SoftDrop::Factor(factor) => f32::max(gravity / factor as f32, 1.0 / 20.0),
+            SoftDrop::Instant => 1.0 / 20.0,
+        }
+    }
+}
+
+// choices that survive a reset
+#[derive(Clone)]
+pub struct Options {
+    pub game_type: GameType,
+    pub scoring: ScoringKind,
+    pub attack_table: AttackTable,
+    pub start_level: usize,
+    pub level_rule: LevelRule,
+    // index into modes::B_TYPE_HEIGHTS
+    pub height: usize,
+    // index into modes::SPRINT_LINES
+    pub sprint_goal: usize,
+    // index into modes::ULTRA_MINUTES
+    pub ultra_time: usize,
+    // index into modes::MARATHON_GOALS
+    pub marathon_goal: usize,
+    // index into modes::DIG_ROWS
+    pub dig_rows: usize,
+    pub dig_messy: bool,
+    // zen pieces only fall when this is set
+    pub zen_gravity: bool,
+    // index into the loaded puzzles
+    pub puzzle: usize,
+    pub stack: StackVisibility,
+    pub line_gravity: LineGravity,
+    pub gravity_curve: GravityCurve,
+    // frames per row by level from the settings file
+    pub custom_gravity: Vec<f32>,
+    pub bindings: Bindings,
+    pub handling: Handling,
+}
+
+// how held directions and soft drop move the piece
+#[derive(Clone, Copy)]
+pub struct Handling {
+    // frames before a held direction repeats, then frames between repeats,
+    // no frames between repeats slides to the wall
+    pub das: usize,
+    pub arr: usize,
+    pub soft_drop: SoftDrop,
+    // a direction held through entry delay and line clears keeps charging,
+    // otherwise it starts over when the next piece enters
+    pub das_charge: bool,
+    // frames after a piece enters before a charged direction repeats
+    pub das_cut: usize,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling {
+            das: DEFAULT_DAS,
+            arr: DEFAULT_ARR,
+            soft_drop: SoftDrop::Factor(20),
+            das_charge: true,
+            das_cut: 0,
+        }
+    }
+}
+
+impl Handling {
+    fn apply_config(&mut self, config: &Config) {
+        if let Some(das) = config.get("das").and_then(|v| v.parse().ok()) {
+            self.das = usize::min(das, MAX_DAS);
+        }
+        if let Some(arr) = config.get("arr").and_then(|v| v.parse().ok()) {
+            self.arr = usize::min(arr, MAX_ARR);
+        }
+        if let Some(soft_drop) = config.get("soft_drop").and_then(SoftDrop::parse) {
+            self.soft_drop = soft_drop;
+        }
+        if let Some(das_charge) = config.get("das_charge").and_then(|v| v.parse().ok()) {
+            self.das_charge = das_charge;
+        }
+        if let Some(das_cut) = config.get("das_cut").and_then(|v| v.parse().ok()) {
+            self.das_cut = usize::min(das_cut, MAX_DAS_CUT);
+        }
+    }
+
+    fn to_config(self) -> String {
+        let mut text = String::new();
+        text += &format!("das = {}\n", self.das);
+        text += &format!("arr = {}\n", self.arr);
+        text += &format!("soft_drop = {}\n", self.soft_drop.name().to_lowercase());
+        text += &format!("das_charge = {}\n", self.das_charge);
+        text += &format!("das_cut = {}\n", self.das_cut);
+
+        text
+    }
+}
+
+impl Options {
+    // used for both the settings file and the remembered menu choices
+    pub fn apply_config(&mut self, config: &Config) {
+        self.bindings.apply_config(config);
+        self.handling.apply_config(config);
+        if config.get("custom_gravity").is_some() {
+            self.custom_gravity = config.get_list("custom_gravity");
+        }
+        if let Some(name) = config.get("game_type") {
+            if let Some(game_type) = GameType::all()
+                .into_iter()
+                .find(|t| t.name().eq_ignore_ascii_case(name))
+            {
+                self.game_type = game_type;
+            }
+            if name.eq_ignore_ascii_case("custom") {
+                self.game_type = GameType::Custom(0);
+            }
+        }
+        // may come without game_type when only the custom mode differs
+        if let GameType::Custom(_) = self.game_type {
+            if let Some(idx) = config.get("custom_mode").and_then(|v| v.parse().ok()) {
+                self.game_type = GameType::Custom(idx);
+            }
+        }
+        if let Some(level) = config.get("start_level").and_then(|v| v.parse().ok()) {
+            self.start_level = usize::min(level, 19);
+        }
+        if let Some(height) = config.get("height").and_then(|v| v.parse().ok()) {
+            self.height = usize::min(height, B_TYPE_HEIGHTS.len() - 1);
+        }
+        if let Some(goal) = config.get("sprint_goal").and_then(|v| v.parse().ok()) {
+            self.sprint_goal = usize::min(goal, SPRINT_LINES.len() - 1);
+        }
+        if let Some(time) = config.get("ultra_time").and_then(|v| v.parse().ok()) {
+            self.ultra_time = usize::min(time, ULTRA_MINUTES.len() - 1);
+        }
+        if let Some(goal) = config.get("marathon_goal").and_then(|v| v.parse().ok()) {
+            self.marathon_goal = usize::min(goal, MARATHON_GOALS.len() - 1);
+        }
+        if let Some(rows) = config.get("dig_rows").and_then(|v| v.parse().ok()) {
+            self.dig_rows = usize::min(rows, DIG_ROWS.len() - 1);
+        }
+        if let Some(messy) = config.get("dig_messy").and_then(|v| v.parse().ok()) {
+            self.dig_messy = messy;
+        }
+        if let Some(gravity) = config.get("zen_gravity").and_then(|v| v.parse().ok()) {
+            self.zen_gravity = gravity;
+        }
+        if let Some(puzzle) = config.get("puzzle").and_then(|v| v.parse().ok()) {
+            self.puzzle = puzzle;
+        }
+        if let Some(name) = config.get("stack") {
+            for stack in StackVisibility::ALL {
+                if stack.name().eq_ignore_ascii_case(name) {
+                    self.stack = stack;
+                }
+            }
+        }
+        if let Some(name) = config.get("line_gravity") {
+            for line_gravity in LineGravity::ALL {
+                if line_gravity.name().eq_ignore_ascii_case(name) {
+                    self.line_gravity = line_gravity;
+                }
+            }
+        }
+        match config.get("level_rule") {
+            Some(name) if name.eq_ignore_ascii_case("nes") => self.level_rule = LevelRule::Nes,
+            Some(name) if name.eq_ignore_ascii_case("guideline") => {
+                self.level_rule = LevelRule::Guideline
+            }
+            _ => {}
+        }
+        match config.get("gravity") {
+            Some(name) if name.eq_ignore_ascii_case("nes") => {
+                self.gravity_curve = GravityCurve::Nes
+            }
+            Some(name) if name.eq_ignore_ascii_case("guideline") => {
+                self.gravity_curve = GravityCurve::Guideline
+            }
+            Some(name) if name.eq_ignore_ascii_case("tgm") => {
+                self.gravity_curve = GravityCurve::Tgm
+            }
+            Some(name)
+                if name.eq_ignore_ascii_case("custom") && !self.custom_gravity.is_empty() =>
+            {
+                self.gravity_curve = GravityCurve::Custom(self.custom_gravity.clone())
+            }
+            _ => {}
+        }
+        if let Some(name) = config.get("scoring") {
+            for kind in [ScoringKind::Nes, ScoringKind::Guideline, ScoringKind::Tgm] {
+                if kind.system().name().eq_ignore_ascii_case(name) {
+                    self.scoring = kind;
+                }
+            }
+        }
+        if let Some(name) = config.get("attack") {
+            if let Some(table) = AttackTable::presets()
+                .into_iter()
+                .find(|t| t.name.eq_ignore_ascii_case(name))
+            {
+                self.attack_table = table;
+            }
+        }
+    }
+
+    // only what differs from the settings file is saved, so later edits to
+    // the settings file still apply
+    pub fn to_config(&self, settings: &Options) -> String {
+        let lines = self.choices_config() + &self.handling.to_config() + &self.bindings.to_config();
+        let defaults = settings.choices_config()
+            + &settings.handling.to_config()
+            + &settings.bindings.to_config();
+        let mut text = String::new();
+        for line in lines.lines() {
+            if !defaults.lines().any(|default| default == line) {
+                text += line;
+                text += "\n";
+            }
+        }
+
+        text
+    }
+
+    fn choices_config(&self) -> String {
+        let mut text = String::new();
+        text += &format!("game_type = {}\n", self.game_type.name().to_lowercase());
+        if let GameType::Custom(idx) = self.game_type {
+            text += &format!("custom_mode = {}\n", idx);
+        }
+        text += &format!("start_level = {}\n", self.start_level);
+        text += &format!("level_rule = {}\n", self.level_rule.name().to_lowercase());
+        text += &format!("height = {}\n", self.height);
+        text += &format!("sprint_goal = {}\n", self.sprint_goal);
+        text += &format!("ultra_time = {}\n", self.ultra_time);
+        text += &format!("marathon_goal = {}\n", self.marathon_goal);
+        text += &format!("dig_rows = {}\n", self.dig_rows);
+        text += &format!("dig_messy = {}\n", self.dig_messy);
+        text += &format!("zen_gravity = {}\n", self.zen_gravity);
+        text += &format!("puzzle = {}\n", self.puzzle);
+        text += &format!("stack = {}\n", self.stack.name().to_lowercase());
+        text += &format!(
+            "line_gravity = {}\n",
+            self.line_gravity.name().to_lowercase()
+        );
+        text += &format!("gravity = {}\n", self.gravity_curve.name().to_lowercase());
+        text += &format!(
+            "scoring = {}\n",
+            self.scoring.system().name().to_lowercase()
+        );
+        text += &format!("attack = {}\n", self.attack_table.name.to_lowercase());
+
+        text
+    }
+
+    // the custom curve is only offered when the settings file has one
+    pub fn next_gravity_curve(&self, forward: bool) -> GravityCurve {
+        let mut curves = vec![
+            GravityCurve::Nes,
+            GravityCurve::Guideline,
+            GravityCurve::Tgm,
+        ];
+        if !self.custom_gravity.is_empty() {
+            curves.push(GravityCurve::Custom(self.custom_gravity.clone()));
+        }
+        let len = curves.len();
+        let current = curves
+            .iter()
+            .position(|c| c.name() == self.gravity_curve.name())
+            .unwrap_or(0);
+        let idx = if forward {
+            (current + 1) % len
+        } else {
+            (current + len - 1) % len
+        };
+
+        curves[idx].clone()
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            game_type: GameType::Marathon,
+            scoring: ScoringKind::Guideline,
+            attack_table: AttackTable::guideline(),
+            start_level: 0,
+            level_rule: LevelRule::Nes,
+            height: 0,
+            sprint_goal: 1,
+            ultra_time: 0,
+            marathon_goal: 0,
+            dig_rows: 1,
+            dig_messy: false,
+            zen_gravity: false,
+            puzzle: 0,
+            stack: StackVisibility::Visible,
+            line_gravity: LineGravity::Naive,
+            gravity_curve: GravityCurve::Nes,
+            custom_gravity: Vec::new(),
+            bindings: Bindings::default(),
+            handling: Handling::default(),
+        }
+    }
+}
+
+pub fn level_for_lines(rule: LevelRule, start_level: usize, lines: usize) -> usize {
+    match rule {
+        LevelRule::Nes => {
+            let first = usize::min(
+                start_level * 10 + 10,
+                usize::max(100, (start_level * 10).saturating_sub(50)),
+            );
+            if lines < first {
+                start_level
+            } else {
+                start_level + 1 + (lines - first) / 10
+            }
+        }
+        LevelRule::Guideline => start_level + lines / 10,
+    }
 }
 
 pub fn get_level_gravity_max(level: usize) -> f32 {
@@ -185,8 +865,88 @@ pub fn get_level_gravity_max(level: usize) -> f32 {
     }
 }
 
-pub fn get_game_state(mode: GameMode) -> GameState {
+#[derive(Clone, PartialEq)]
+pub enum GravityCurve {
+    Nes,
+    // (0.8 - (level - 1) * 0.007)^(level - 1) seconds per row
+    Guideline,
+    // internal gravity in 1/256 rows per frame
+    Tgm,
+    Custom(Vec<f32>),
+}
+
+impl GravityCurve {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GravityCurve::Nes => "NES",
+            GravityCurve::Guideline => "GUIDELINE",
+            GravityCurve::Tgm => "TGM",
+            GravityCurve::Custom(_) => "CUSTOM",
+        }
+    }
+
+    // anything below one frame moves several rows per frame, down to 20G
+    pub fn frames_per_row(&self, level: usize) -> f32 {
+        let frames = match self {
+            GravityCurve::Nes => get_level_gravity_max(level),
+            GravityCurve::Guideline => {
+                let level = usize::min(level + 1, 20) as f32;
+                let seconds = (0.8 - (level - 1.0) * 0.007).powf(level - 1.0);
+                seconds * 60.0
+            }
+            GravityCurve::Tgm => 256.0 / get_tgm_internal_gravity(level),
+            GravityCurve::Custom(frames) => match frames.get(level).or(frames.last()) {
+                Some(frames) => *frames,
+                None => get_level_gravity_max(level),
+            },
+        };
+
+        f32::max(frames, 1.0 / 20.0)
+    }
+}
+
+pub fn get_tgm_internal_gravity(level: usize) -> f32 {
+    match level {
+        0..=29 => 4.0,
+        30..=34 => 6.0,
+        35..=39 => 8.0,
+        40..=49 => 10.0,
+        50..=59 => 12.0,
+        60..=69 => 16.0,
+        70..=79 => 32.0,
+        80..=89 => 48.0,
+        90..=99 => 64.0,
+        100..=119 => 80.0,
+        120..=139 => 96.0,
+        140..=159 => 112.0,
+        160..=169 => 128.0,
+        170..=199 => 144.0,
+        200..=219 => 4.0,
+        220..=229 => 32.0,
+        230..=232 => 64.0,
+        233..=235 => 96.0,
+        236..=238 => 128.0,
+        239..=242 => 160.0,
+        243..=246 => 192.0,
+        247..=250 => 224.0,
+        251..=299 => 256.0,
+        300..=329 => 512.0,
+        330..=359 => 768.0,
+        360..=399 => 1024.0,
+        400..=419 => 1280.0,
+        420..=449 => 1024.0,
+        450..=499 => 768.0,
+        _ => 5120.0,
+    }
+}
+
+pub fn get_game_state(mode: GameMode, options: Options) -> GameState {
     let tetrominos = spawner::tetromino_set();
+    let start_level = if options.game_type.has_level() {
+        options.start_level
+    } else {
+        0
+    };
     let next = spawner::random_tetrominos(&tetrominos, 10);
     let current = spawner::spawn_tetromino(&tetrominos);
     GameState {
@@ -202,18 +962,32 @@ pub fn get_game_state(mode: GameMode) -> GameState {
         },
         last_update: 0.0,
         score: Score {
-            level: 0,
+            level: start_level,
             lines: 0,
             val: 0,
             topout: false,
+            combo: 0,
+            b2b: false,
+            attack: 0,
+            frames: 0,
+            bonus: 0,
+            finesse_faults: 0,
+            record: None,
+            rank: None,
+            solved: false,
+            chain: 0,
         },
         key_info: KeyInfo {
-            auto_shift_start: 0.0,
-            auto_shift: (None, 0.0),
+            auto_shift: None,
+            das_timer: 0,
+            arr_timer: 0,
+            entered: None,
+            das_cut: 0,
         },
         gravity: Gravity {
             meter: 0.0,
-            max: get_level_gravity_max(0),
+            max: options.gravity_curve.frames_per_row(start_level),
+            soft_drop: false,
         },
         hold: None,
         textures: Texture2D::empty(),
@@ -223,8 +997,45 @@ pub fn get_game_state(mode: GameMode) -> GameState {
             val: 0,
             creation: 0,
         },
+        callouts: Vec::new(),
         line_clear: None,
         game_mode: mode,
         last_input: FrameInput::None,
+        scoring: options.scoring.system(),
+        options,
+        menu_cursor: 0,
+        high_scores: Vec::new(),
+        endless: false,
+        garbage_timer: 0,
+        garbage_interval: modes::SURVIVAL_START_INTERVAL,
+        delays: Delays::default(),
+        section_splits: Vec::new(),
+        puzzles: Vec::new(),
+        custom_modes: Vec::new(),
+        settings: Options::default(),
+        randomizer: Randomizer::Random,
+        bag: Vec::new(),
+        spawns: 0,
+        idle: 0,
+        binding: false,
+        demo: Bot::default(),
+    }
+}
+
+pub fn reset_game_state(gs: &mut GameState, mode: GameMode) {
+    let textures = gs.textures;
+    let font = gs.font;
+    let options = gs.options.clone();
+    let puzzles = std::mem::take(&mut gs.puzzles);
+    let custom_modes = std::mem::take(&mut gs.custom_modes);
+    let settings = std::mem::take(&mut gs.settings);
+    *gs = get_game_state(mode, options);
+    gs.textures = textures;
+    gs.font = font;
+    gs.puzzles = puzzles;
+    gs.custom_modes = custom_modes;
+    gs.settings = settings;
+    if gs.game_mode == GameMode::Play {
+        modes::start(gs);
     }
 }
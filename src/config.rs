@@ -0,0 +1,70 @@
+/*
+ Settings file
+ One `key = value` pair per line, `#` starts a comment.
+*/
+use std::collections::HashMap;
+
+pub const SETTINGS_PATH: &str = "settings.cfg";
+// last menu choices, written by the game
+pub const OPTIONS_PATH: &str = "options.sav";
+// personal bests and high scores
+pub const RECORDS_PATH: &str = "records.sav";
+
+#[derive(Clone, Default)]
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Config {
+        let mut values = HashMap::new();
+        for line in text.lines() {
+            let line = match line.find('#') {
+                Some(idx) => &line[..idx],
+                None => line,
+            };
+            if let Some((key, value)) = line.split_once('=') {
+                values.insert(key.trim().to_lowercase(), value.trim().to_string());
+            }
+        }
+
+        Config { values }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|v| v.as_str())
+    }
+
+    pub fn set(&mut self, key: &str, value: String) {
+        self.values.insert(key.to_lowercase(), value);
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut keys: Vec<&String> = self.values.keys().collect();
+        keys.sort();
+        keys.iter()
+            .map(|k| format!("{} = {}\n", k, self.values[*k]))
+            .collect()
+    }
+
+    pub fn get_list(&self, key: &str) -> Vec<f32> {
+        match self.get(key) {
+            Some(value) => value
+                .split(',')
+                .filter_map(|v| v.trim().parse::<f32>().ok())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+// saving is best effort, the web build has no file system
+pub fn load(path: &str) -> Option<Config> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|text| Config::parse(&text))
+}
+
+pub fn save(path: &str, text: &str) {
+    let _ = std::fs::write(path, text);
+}
@@ -0,0 +1,350 @@
+/*
+ Controls
+ Game actions and the keys and pad buttons bound to them. Bindings are read
+ from the settings file as `key_<action> = <key>, <key>` and
+ `pad_<action> = <button>`, and changed on the controls screen.
+*/
+use std::collections::HashMap;
+
+use macroquad::prelude::{is_key_down, is_key_pressed, is_key_released, KeyCode};
+
+use super::{
+    config::Config,
+    gamepad::{self, Button},
+};
+
+// how far the stick is pushed before it counts as the d-pad
+pub const DEFAULT_DEADZONE: f32 = 0.5;
+pub const DEADZONE_STEP: f32 = 0.05;
+const MIN_DEADZONE: f32 = 0.1;
+const MAX_DEADZONE: f32 = 0.9;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    RotateCw,
+    RotateCcw,
+    Rotate180,
+    SoftDrop,
+    HardDrop,
+    Hold,
+    Pause,
+    Restart,
+    Debug,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::RotateCw,
+        Action::RotateCcw,
+        Action::Rotate180,
+        Action::SoftDrop,
+        Action::HardDrop,
+        Action::Hold,
+        Action::Pause,
+        Action::Restart,
+        Action::Debug,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "MOVE LEFT",
+            Action::MoveRight => "MOVE RIGHT",
+            Action::RotateCw => "ROTATE CW",
+            Action::RotateCcw => "ROTATE CCW",
+            Action::Rotate180 => "ROTATE 180",
+            Action::SoftDrop => "SOFT DROP",
+            Action::HardDrop => "HARD DROP",
+            Action::Hold => "HOLD",
+            Action::Pause => "PAUSE",
+            Action::Restart => "RESTART",
+            Action::Debug => "DEBUG",
+        }
+    }
+
+    // settings file keys, key_move_left and pad_move_left
+    fn config_key(self, prefix: &str) -> String {
+        format!(
+            "{}_{}",
+            prefix,
+            self.name().to_lowercase().replace(' ', "_")
+        )
+    }
+
+    fn default_keys(self) -> Vec<KeyCode> {
+        match self {
+            Action::MoveLeft => vec![KeyCode::Left],
+            Action::MoveRight => vec![KeyCode::Right],
+            Action::RotateCw => vec![KeyCode::Up],
+            Action::RotateCcw => vec![KeyCode::Z],
+            Action::Rotate180 => vec![KeyCode::A],
+            Action::SoftDrop => vec![KeyCode::Down],
+            Action::HardDrop => vec![KeyCode::Space],
+            Action::Hold => vec![KeyCode::C],
+            Action::Pause => vec![KeyCode::P],
+            Action::Restart => vec![KeyCode::R],
+            Action::Debug => vec![KeyCode::G],
+        }
+    }
+
+    fn default_buttons(self) -> Vec<Button> {
+        match self {
+            Action::MoveLeft => vec![Button::DPadLeft],
+            Action::MoveRight => vec![Button::DPadRight],
+            Action::RotateCw => vec![Button::East],
+            Action::RotateCcw => vec![Button::South],
+            Action::Rotate180 => vec![Button::North],
+            Action::SoftDrop => vec![Button::DPadDown],
+            Action::HardDrop => vec![Button::DPadUp],
+            Action::Hold => vec![Button::LeftBumper, Button::RightBumper],
+            Action::Pause => vec![Button::Start],
+            Action::Restart => vec![Button::Select],
+            Action::Debug => vec![],
+        }
+    }
+}
+
+// keys that can be bound, named as in the settings file
+const KEYS: [KeyCode; 72] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Escape,
+    KeyCode::LeftShift,
+    KeyCode::RightShift,
+    KeyCode::LeftControl,
+    KeyCode::RightControl,
+    KeyCode::LeftAlt,
+    KeyCode::RightAlt,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Apostrophe,
+    KeyCode::LeftBracket,
+    KeyCode::RightBracket,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::Kp0,
+    KeyCode::Kp1,
+    KeyCode::Kp2,
+    KeyCode::Kp3,
+    KeyCode::Kp4,
+    KeyCode::Kp5,
+    KeyCode::Kp6,
+    KeyCode::Kp7,
+    KeyCode::Kp8,
+    KeyCode::Kp9,
+    KeyCode::KpEnter,
+    KeyCode::Insert,
+];
+
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key).to_uppercase()
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    KEYS.iter()
+        .find(|k| key_name(**k).eq_ignore_ascii_case(name))
+        .copied()
+}
+
+pub fn can_bind(key: KeyCode) -> bool {
+    KEYS.contains(&key)
+}
+
+// entries found in more than one list
+fn doubled<T: Copy + PartialEq>(lists: Vec<&[T]>) -> Vec<T> {
+    let mut doubled = Vec::new();
+    for (i, a) in lists.iter().enumerate() {
+        for b in lists.iter().skip(i + 1) {
+            for entry in a.iter() {
+                if b.contains(entry) && !doubled.contains(entry) {
+                    doubled.push(*entry);
+                }
+            }
+        }
+    }
+
+    doubled
+}
+
+#[derive(Clone)]
+pub struct Bindings {
+    keys: HashMap<Action, Vec<KeyCode>>,
+    buttons: HashMap<Action, Vec<Button>>,
+    pub deadzone: f32,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        Bindings {
+            keys: Action::ALL.iter().map(|a| (*a, a.default_keys())).collect(),
+            buttons: Action::ALL
+                .iter()
+                .map(|a| (*a, a.default_buttons()))
+                .collect(),
+            deadzone: DEFAULT_DEADZONE,
+        }
+    }
+}
+
+impl Bindings {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+
+    pub fn buttons(&self, action: Action) -> &[Button] {
+        self.buttons
+            .get(&action)
+            .map_or(&[], |buttons| buttons.as_slice())
+    }
+
+    pub fn bind(&mut self, action: Action, key: KeyCode) {
+        let keys = self.keys.entry(action).or_default();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    pub fn bind_button(&mut self, action: Action, button: Button) {
+        let buttons = self.buttons.entry(action).or_default();
+        if !buttons.contains(&button) {
+            buttons.push(button);
+        }
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.keys.insert(action, Vec::new());
+        self.buttons.insert(action, Vec::new());
+    }
+
+    pub fn adjust_deadzone(&mut self, step: f32) {
+        self.deadzone = (self.deadzone + step).clamp(MIN_DEADZONE, MAX_DEADZONE);
+    }
+
+    // keys bound to more than one action
+    pub fn conflicts(&self) -> Vec<KeyCode> {
+        doubled(Action::ALL.iter().map(|a| self.keys(*a)).collect())
+    }
+
+    pub fn button_conflicts(&self) -> Vec<Button> {
+        doubled(Action::ALL.iter().map(|a| self.buttons(*a)).collect())
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.keys(action).iter().any(|k| is_key_pressed(*k))
+            || self
+                .buttons(action)
+                .iter()
+                .any(|b| gamepad::is_button_pressed(*b))
+    }
+
+    pub fn down(&self, action: Action) -> bool {
+        self.keys(action).iter().any(|k| is_key_down(*k))
+            || self
+                .buttons(action)
+                .iter()
+                .any(|b| gamepad::is_button_down(*b))
+    }
+
+    // the last of the keys and buttons was let go
+    pub fn released(&self, action: Action) -> bool {
+        (self.keys(action).iter().any(|k| is_key_released(*k))
+            || self
+                .buttons(action)
+                .iter()
+                .any(|b| gamepad::is_button_released(*b)))
+            && !self.down(action)
+    }
+
+    // unknown names are skipped, an action listed without any is left unbound
+    pub fn apply_config(&mut self, config: &Config) {
+        for action in Action::ALL {
+            if let Some(value) = config.get(&action.config_key("key")) {
+                let keys = value
+                    .split(',')
+                    .map(|name| name.trim())
+                    .filter_map(parse_key)
+                    .collect();
+                self.keys.insert(action, keys);
+            }
+            if let Some(value) = config.get(&action.config_key("pad")) {
+                let buttons = value
+                    .split(',')
+                    .map(|name| name.trim())
+                    .filter_map(Button::parse)
+                    .collect();
+                self.buttons.insert(action, buttons);
+            }
+        }
+        if let Some(deadzone) = config.get("pad_deadzone").and_then(|v| v.parse().ok()) {
+            self.deadzone = f32::clamp(deadzone, MIN_DEADZONE, MAX_DEADZONE);
+        }
+    }
+
+    pub fn to_config(&self) -> String {
+        let mut text = String::new();
+        for action in Action::ALL {
+            let names: Vec<String> = self
+                .keys(action)
+                .iter()
+                .map(|k| key_name(*k).to_lowercase())
+                .collect();
+            text += &format!("{} = {}\n", action.config_key("key"), names.join(", "));
+            let names: Vec<String> = self
+                .buttons(action)
+                .iter()
+                .map(|b| b.name().to_lowercase())
+                .collect();
+            text += &format!("{} = {}\n", action.config_key("pad"), names.join(", "));
+        }
+        text += &format!("pad_deadzone = {:.2}\n", self.deadzone);
+
+        text
+    }
+}
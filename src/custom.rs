@@ -0,0 +1,198 @@
+/*
+ Custom modes
+ Modes read from a text file and listed after the built in game types. A mode
+ starts with its name in brackets, followed by `key = value` lines. The
+ level_rule, gravity and scoring keys are read like the settings file.
+
+ [CHEESE DRILL]
+ description = CLEAR 10 ROWS OF CHEESE IN 40 PIECES
+ goal = garbage          # lines 40, time 2:00, score 50000 or garbage (needs a board)
+ end = pieces 40         # topout, time 3:00 or pieces 40
+ board = cheese 10       # empty, garbage 8, cheese 10 or messy 10
+ randomizer = bag        # random or bag
+ hold = no
+ gravity = guideline
+*/
+use super::{
+    components::{GravityCurve, LevelRule, Options, Randomizer},
+    config::Config,
+    modes::format_ticks,
+    scoring::ScoringKind,
+};
+
+pub const CUSTOM_MODES_PATH: &str = "assets/modes.txt";
+// leaves room to spawn on a full starting board
+const MAX_BOARD_ROWS: usize = 18;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Goal {
+    Lines(usize),
+    // frames to survive
+    Time(usize),
+    Score(usize),
+    // every garbage row cleared
+    Garbage,
+}
+
+impl Goal {
+    pub fn name(self) -> String {
+        match self {
+            Goal::Lines(lines) => format!("CLEAR {} LINES", lines),
+            Goal::Time(frames) => format!("SURVIVE {}", format_ticks(frames)),
+            Goal::Score(score) => format!("SCORE {}", score),
+            Goal::Garbage => "CLEAR THE GARBAGE".to_string(),
+        }
+    }
+
+    fn parse(text: &str) -> Option<Goal> {
+        let text = text.to_lowercase();
+        match text.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["lines", lines] => lines.parse().ok().map(Goal::Lines),
+            ["time", time] => parse_time(time).map(Goal::Time),
+            ["score", score] => score.parse().ok().map(Goal::Score),
+            ["garbage"] => Some(Goal::Garbage),
+            _ => None,
+        }
+    }
+}
+
+// the game ends without reaching the goal
+#[derive(Clone, Copy, PartialEq)]
+pub enum End {
+    Topout,
+    Time(usize),
+    Pieces(usize),
+}
+
+impl End {
+    fn parse(text: &str) -> Option<End> {
+        let text = text.to_lowercase();
+        match text.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["topout"] => Some(End::Topout),
+            ["time", time] => parse_time(time).map(End::Time),
+            ["pieces", pieces] => pieces.parse().ok().map(End::Pieces),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Board {
+    Empty,
+    Garbage(usize),
+    Cheese(usize),
+    Messy(usize),
+}
+
+impl Board {
+    fn parse(text: &str) -> Option<Board> {
+        let text = text.to_lowercase();
+        let rows = |rows: &str| rows.parse().ok().map(|r| usize::min(r, MAX_BOARD_ROWS));
+        match text.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["empty"] => Some(Board::Empty),
+            ["garbage", r] => rows(r).map(Board::Garbage),
+            ["cheese", r] => rows(r).map(Board::Cheese),
+            ["messy", r] => rows(r).map(Board::Messy),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CustomMode {
+    pub name: String,
+    pub description: String,
+    pub goal: Goal,
+    pub end: End,
+    pub board: Board,
+    pub level: usize,
+    pub level_rule: LevelRule,
+    pub gravity_curve: GravityCurve,
+    pub scoring: ScoringKind,
+    pub randomizer: Randomizer,
+    pub hold_allowed: bool,
+}
+
+impl CustomMode {
+    fn from_config(name: &str, config: &Config) -> Option<CustomMode> {
+        let goal = Goal::parse(config.get("goal")?)?;
+        let board = config
+            .get("board")
+            .and_then(Board::parse)
+            .unwrap_or(Board::Empty);
+        // nothing to clear would reach the goal on the first lock
+        let garbage_rows = match board {
+            Board::Empty => 0,
+            Board::Garbage(rows) | Board::Cheese(rows) | Board::Messy(rows) => rows,
+        };
+        if goal == Goal::Garbage && garbage_rows == 0 {
+            return None;
+        }
+        let mut rules = Options::default();
+        rules.apply_config(config);
+
+        Some(CustomMode {
+            name: name.to_uppercase(),
+            description: match config.get("description") {
+                Some(description) => description.to_uppercase(),
+                None => goal.name(),
+            },
+            goal,
+            end: config
+                .get("end")
+                .and_then(End::parse)
+                .unwrap_or(End::Topout),
+            board,
+            level: config
+                .get("level")
+                .and_then(|v| v.parse().ok())
+                .map_or(0, |level: usize| usize::min(level, 19)),
+            level_rule: rules.level_rule,
+            gravity_curve: rules.gravity_curve,
+            scoring: rules.scoring,
+            randomizer: match config.get("randomizer") {
+                Some(name) if name.eq_ignore_ascii_case("bag") => Randomizer::Bag,
+                _ => Randomizer::Random,
+            },
+            hold_allowed: !matches!(config.get("hold"), Some(hold) if hold.eq_ignore_ascii_case("no")),
+        })
+    }
+}
+
+// minutes and seconds as 2:00, or seconds alone
+fn parse_time(text: &str) -> Option<usize> {
+    let seconds: usize = match text.split_once(':') {
+        Some((minutes, seconds)) => {
+            minutes.parse::<usize>().ok()? * 60 + seconds.parse::<usize>().ok()?
+        }
+        None => text.parse().ok()?,
+    };
+
+    Some(seconds * 60)
+}
+
+// modes without a goal, or with garbage to clear but none on the board, are left out
+pub fn parse(text: &str) -> Vec<CustomMode> {
+    let mut modes = Vec::new();
+    let mut current: Option<(String, String)> = None;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            if let Some((name, body)) = current.take() {
+                modes.extend(CustomMode::from_config(&name, &Config::parse(&body)));
+            }
+            current = Some((
+                trimmed[1..trimmed.len() - 1].trim().to_string(),
+                String::new(),
+            ));
+        } else if let Some((_, body)) = &mut current {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+    if let Some((name, body)) = current {
+        modes.extend(CustomMode::from_config(&name, &Config::parse(&body)));
+    }
+
+    modes
+}
@@ -0,0 +1,192 @@
+/*
+ Demo
+ Attract mode shown after the title screen sits idle. A bot plays marathon,
+ placing each piece where a few board features score best.
+*/
+use super::{
+    collision::{completed_lines, should_commit_tetromino},
+    components::{
+        reset_game_state, FrameInput, GameMode, GameType, Ghost, Options, WELL_HEIGHT, WELL_WIDTH,
+    },
+    input::{move_left, move_right},
+    modes, srs, xy_idx, Block, GameState, Tetromino, LIGHT,
+};
+
+// ten seconds on the title screen, then a minute of demo
+pub const DEMO_IDLE_FRAMES: usize = 600;
+pub const DEMO_FRAMES: usize = 3600;
+// frames between bot inputs so the demo can be followed
+const BOT_INPUT_DELAY: usize = 6;
+// gives up on a placement it cannot reach and drops the piece
+const BOT_MAX_INPUTS: usize = 12;
+
+#[derive(Clone, Default)]
+pub struct Bot {
+    // pieces placed when the target was chosen
+    pub planned: Option<usize>,
+    pub target: Option<Tetromino>,
+    pub timer: usize,
+    pub inputs: usize,
+    // options the player had picked before the demo
+    pub return_to: Option<Options>,
+}
+
+// plays a plain marathon whatever the player has set up
+pub fn start(gs: &mut GameState) {
+    let return_to = std::mem::replace(
+        &mut gs.options,
+        Options {
+            game_type: GameType::Marathon,
+            ..Options::default()
+        },
+    );
+    reset_game_state(gs, GameMode::Play);
+    gs.game_mode = GameMode::Demo;
+    gs.demo.return_to = Some(return_to);
+}
+
+pub fn stop(gs: &mut GameState) {
+    if let Some(options) = gs.demo.return_to.take() {
+        gs.options = options;
+    }
+    reset_game_state(gs, GameMode::Title);
+}
+
+fn drop_to_floor(tetromino: &Tetromino, placed: &Vec<Option<Block>>) -> Tetromino {
+    let mut dropped = *tetromino;
+    while !should_commit_tetromino(&dropped, &dropped.pos, placed) {
+        dropped.pos.y -= 1.0;
+    }
+
+    dropped
+}
+
+// aggregate height, holes and bumpiness against cleared lines
+fn evaluate(placed: &Vec<Option<Block>>) -> f32 {
+    let lines = completed_lines(placed).len() as f32;
+    let mut heights = [0.0; WELL_WIDTH];
+    let mut holes = 0.0;
+    for (x, height) in heights.iter_mut().enumerate() {
+        let mut covered = false;
+        for y in 0..WELL_HEIGHT {
+            if placed[y * WELL_WIDTH + x].is_some() {
+                if !covered {
+                    *height = (WELL_HEIGHT - y) as f32;
+                }
+                covered = true;
+            } else if covered {
+                holes += 1.0;
+            }
+        }
+    }
+    let aggregate: f32 = heights.iter().sum();
+    let bumpiness: f32 = heights.windows(2).map(|w| (w[0] - w[1]).abs()).sum();
+
+    -0.51 * aggregate + 0.76 * lines - 0.36 * holes - 0.18 * bumpiness
+}
+
+// tries every rotation and column from where the piece is now
+fn plan(gs: &GameState) -> Option<Tetromino> {
+    let mut best: Option<(f32, Tetromino)> = None;
+    let mut rotated = gs.current;
+    for _ in 0..4 {
+        let mut leftmost = rotated;
+        while let Some(next) = shifted(&leftmost, &gs.placed_blocks, -1.0) {
+            leftmost = next;
+        }
+        let mut column = Some(leftmost);
+        while let Some(candidate) = column {
+            let dropped = drop_to_floor(&candidate, &gs.placed_blocks);
+            let mut placed = gs.placed_blocks.clone();
+            let points = dropped.relative_points(&dropped.pos);
+            if points.iter().all(|p| p.y >= 0.0) {
+                for p in points.iter() {
+                    placed[xy_idx(p.x, p.y)] = Some(Block {
+                        color: LIGHT,
+                        kind: dropped.kind,
+                        garbage: false,
+                        locked: 0,
+                        piece: 0,
+                    });
+                }
+                let score = evaluate(&placed);
+                if best.is_none_or(|(s, _)| score > s) {
+                    best = Some((score, candidate));
+                }
+            }
+            column = shifted(&candidate, &gs.placed_blocks, 1.0);
+        }
+
+        let mut ghost = Ghost {
+            pos: rotated.pos,
+            dirty: false,
+        };
+        srs::rotate(true, &mut rotated, &gs.placed_blocks, &mut ghost);
+    }
+
+    best.map(|(_, target)| target)
+}
+
+fn shifted(tetromino: &Tetromino, placed: &Vec<Option<Block>>, dx: f32) -> Option<Tetromino> {
+    let mut moved = *tetromino;
+    let mut ghost = Ghost {
+        pos: moved.pos,
+        dirty: false,
+    };
+    if dx < 0.0 {
+        move_left(&mut moved, placed, &mut ghost);
+    } else {
+        move_right(&mut moved, placed, &mut ghost);
+    }
+
+    if moved.pos == tetromino.pos {
+        None
+    } else {
+        Some(moved)
+    }
+}
+
+fn same_rotation(a: &Tetromino, b: &Tetromino) -> bool {
+    a.mat == b.mat && a.mat4 == b.mat4
+}
+
+// one input every few frames: rotate, shift, then hard drop
+pub fn drive(gs: &mut GameState) {
+    let entered = gs.current.entry_timer >= gs.delays.entry && gs.line_clear.is_none();
+    if !entered || gs.ghost.dirty {
+        return;
+    }
+
+    let pieces = modes::pieces(gs);
+    if gs.demo.planned != Some(pieces) {
+        gs.demo.planned = Some(pieces);
+        gs.demo.target = plan(gs);
+        gs.demo.inputs = 0;
+    }
+
+    gs.demo.timer += 1;
+    if gs.demo.timer < BOT_INPUT_DELAY {
+        return;
+    }
+    gs.demo.timer = 0;
+
+    let target = match gs.demo.target {
+        Some(target) if gs.demo.inputs < BOT_MAX_INPUTS => target,
+        _ => gs.current,
+    };
+    gs.demo.inputs += 1;
+    if !same_rotation(&gs.current, &target) {
+        srs::rotate(true, &mut gs.current, &gs.placed_blocks, &mut gs.ghost);
+        gs.last_input = FrameInput::Rotate;
+    } else if gs.current.pos.x > target.pos.x {
+        move_left(&mut gs.current, &gs.placed_blocks, &mut gs.ghost);
+        gs.last_input = FrameInput::Move;
+    } else if gs.current.pos.x < target.pos.x {
+        move_right(&mut gs.current, &gs.placed_blocks, &mut gs.ghost);
+        gs.last_input = FrameInput::Move;
+    } else {
+        gs.current.sonic_lock = true;
+        gs.current.hard_dropped = (gs.current.pos.y - gs.ghost.pos.y) as usize;
+        gs.current.pos = gs.ghost.pos;
+    }
+}
@@ -1,17 +1,18 @@
 use std::collections::HashMap;
 
-use crate::components::{
-    GameMode, ScorePopup, ENTRY_DELAY, LINE_CLEAR_DELAY, PIXELS_PER_UNIT, SCORE_TIMEOUT,
-};
+use crate::components::{Callout, GameMode, GameType, ScorePopup, PIXELS_PER_UNIT, SCORE_TIMEOUT};
 
 use super::{
-    Block, GameState, Score, Tetromino, TetrominoType, DARK, GAME_HEIGHT, GAME_WIDTH, LIGHT,
+    attack::attack_per_minute,
+    controls::{self, Action},
+    menu::{handling_items, item_text, option_items},
+    modes, GameState, Score, Tetromino, TetrominoType, DARK, GAME_HEIGHT, GAME_WIDTH, LIGHT,
     WELL_CELL, WELL_CELL_GAP, WELL_HEIGHT, WELL_WIDTH,
 };
 use macroquad::{
     prelude::{
         clear_background, debug, draw_circle, draw_line, draw_rectangle, draw_rectangle_lines,
-        draw_text, draw_texture_ex, get_fps, measure_text, vec2, DrawTextureParams, Rect,
+        draw_text, draw_texture_ex, get_fps, measure_text, vec2, Color, DrawTextureParams, Rect,
         Texture2D, Vec2, BLUE, GRAY, PINK, RED,
     },
     text::{draw_text_ex, Font, TextDimensions, TextParams},
@@ -64,6 +65,21 @@ pub fn draw_block(
     );
 }
 
+// garbage is drawn darker so it stands out from placed tetrominos
+pub fn draw_garbage_block(scl: f32, textures: &Texture2D, x: f32, y: f32) {
+    draw_texture_ex(
+        *textures,
+        x,
+        y,
+        GRAY,
+        DrawTextureParams {
+            dest_size: Some(vec2(1.0 * scl, 1.0 * scl)),
+            source: Some(Rect::new(96.0, 0.0, 16.0, 16.0)),
+            ..Default::default()
+        },
+    );
+}
+
 pub fn draw_tetromino(
     textures: &Texture2D,
     offset: Vec2,
@@ -76,16 +92,17 @@ pub fn draw_tetromino(
     let x = pos.x;
     let y = pos.y;
     let w = (WELL_CELL - WELL_CELL_GAP) * scl;
+    let scale = current.scale as f32;
 
     match current.kind {
         TetrominoType::I | TetrominoType::O => {
             for r in 0..4 {
                 for c in 0..4 {
-                    let dx = x + r as f32;
-                    let dy = WELL_HEIGHT as f32 - (y + c as f32);
+                    let dx = (x + r as f32) * scale;
+                    let dy = WELL_HEIGHT as f32 - (y + c as f32) * scale;
                     if current.mat4.row(r)[c] == 1.0 && dx >= 0.0 && dy >= 0.0 {
                         draw_block(
-                            scl,
+                            scl * scale,
                             textures,
                             (offset.x + dx as f32) * scl,
                             (offset.y + dy as f32) * scl,
@@ -109,11 +126,11 @@ pub fn draw_tetromino(
         _ => {
             for r in 0..3 {
                 for c in 0..3 {
-                    let dx = x + r as f32;
-                    let dy = WELL_HEIGHT as f32 - (y + c as f32);
+                    let dx = (x + r as f32) * scale;
+                    let dy = WELL_HEIGHT as f32 - (y + c as f32) * scale;
                     if current.mat.row(r)[c] == 1.0 && dx >= 0.0 && dy >= 0.0 {
                         draw_block(
-                            scl,
+                            scl * scale,
                             textures,
                             (offset.x + dx as f32) * scl,
                             (offset.y + dy as f32) * scl,
@@ -155,6 +172,9 @@ fn draw_visual_only_tetromino(
     game_pos: &Vec2,
     tetromino: &Tetromino,
 ) {
+    // previews keep the normal size
+    let mut tetromino = *tetromino;
+    tetromino.scale = 1;
     let points = tetromino.relative_points(game_pos);
     for p in points.iter() {
         draw_block(scl, textures, p.x * scl, p.y * scl, tetromino.kind, false);
@@ -191,6 +211,7 @@ fn draw_statistics(
     scl: f32,
     tetrominos: &Vec<Tetromino>,
     statistics: &HashMap<TetrominoType, usize>,
+    score: &Score,
 ) {
     let text = &"STATS".to_string();
     let (params, dims) = text_config.params_and_dims(text, 1.5);
@@ -220,6 +241,10 @@ fn draw_statistics(
             _ => {}
         }
     }
+
+    let apm_text = &format!("APM {:05.1}", attack_per_minute(score.attack, score.frames));
+    let (apm_params, _) = text_config.params_and_dims(apm_text, 1.25);
+    draw_text_ex(apm_text, (x - 0.5) * scl, (y + 18.5) * scl, apm_params);
 }
 
 fn draw_next(
@@ -244,7 +269,9 @@ fn draw_next(
             vec2(0.0, -0.5)
         };
 
-        draw_tetromino(textures, offset, scl, t, &pos, false, &false);
+        let mut t = *t;
+        t.scale = 1;
+        draw_tetromino(textures, offset, scl, &t, &pos, false, &false);
         if i >= 3 {
             break;
         }
@@ -252,20 +279,25 @@ fn draw_next(
     draw_border(textures, scl, vec2(GAME_WIDTH - 8.0, 1.0), 5.0, 13.0);
 }
 
-fn draw_placed(
-    textures: &Texture2D,
-    offset: Vec2,
-    scl: f32,
-    placed: &Vec<Option<Block>>,
-    debug: &bool,
-) {
+fn draw_placed(gs: &GameState, offset: Vec2) {
+    let textures = &gs.textures;
+    let scl = gs.scl;
+    let debug = &gs.debug;
     let w = (WELL_CELL - WELL_CELL_GAP) * scl;
-    for (idx, block) in placed.iter().enumerate() {
+    // big blocks are drawn once from their top left cell
+    let scale = gs.current.scale;
+    let size = scl * scale as f32;
+    for (idx, block) in gs.placed_blocks.iter().enumerate() {
         match block {
             Some(block) => {
                 let color = if *debug { GRAY } else { block.color };
                 let x = idx as usize % WELL_WIDTH;
                 let y = idx as usize / WELL_WIDTH;
+                let alpha = modes::block_alpha(gs, block);
+                if !*debug && (alpha <= 0.0 || !x.is_multiple_of(scale) || !y.is_multiple_of(scale))
+                {
+                    continue;
+                }
 
                 if *debug {
                     draw_rectangle(
@@ -275,9 +307,16 @@ fn draw_placed(
                         w,
                         GRAY,
                     );
+                } else if block.garbage {
+                    draw_garbage_block(
+                        size,
+                        textures,
+                        (offset.x + x as f32) * scl,
+                        (offset.y + y as f32) * scl,
+                    );
                 } else {
                     draw_block(
-                        scl,
+                        size,
                         textures,
                         (offset.x + x as f32) * scl,
                         (offset.y + y as f32) * scl,
@@ -285,38 +324,51 @@ fn draw_placed(
                         false,
                     );
                 }
+                // fading blocks sink into the background
+                if alpha < 1.0 && !*debug {
+                    draw_rectangle(
+                        (offset.x + x as f32) * scl,
+                        (offset.y + y as f32) * scl,
+                        size,
+                        size,
+                        Color {
+                            a: 1.0 - alpha,
+                            ..DARK
+                        },
+                    );
+                }
             }
             _ => {}
         }
     }
 }
 
-fn draw_score(textures: &Texture2D, text_config: &TextParamsConfig, scl: f32, score: &Score) {
-    let lines_head_y = (GAME_HEIGHT - 12.0) * scl;
-    let lines_head = &"LINES".to_string();
-    let lines_body = &format!("{:0>3}", score.lines).to_string();
-    let (base_params, base_dimensions) = text_config.params_and_dims(lines_head, 1.25);
-    // Everything is measured relative to the first heading
-    let x = (GAME_WIDTH - 6.0 - base_dimensions.width / 2.0 / scl) * scl;
-    draw_text_ex(lines_head, x, lines_head_y, base_params);
-    draw_text_ex(lines_body, x, lines_head_y + 1.0 * scl, base_params);
-
-    let level_head_y = (GAME_HEIGHT - 9.5) * scl;
-    let level_head = &"LEVEL".to_string();
-    let level_body = &format!("{:0>2}", score.level).to_string();
-    draw_text_ex(level_head, x, level_head_y, base_params);
-    draw_text_ex(level_body, x, level_head_y + 1.0 * scl, base_params);
-
-    let score_head = &"SCORE".to_string();
-    let score_head_y = (GAME_HEIGHT - 7.0) * scl;
-    draw_text_ex(score_head, x, score_head_y, base_params);
+fn draw_score(
+    textures: &Texture2D,
+    text_config: &TextParamsConfig,
+    scl: f32,
+    score: &Score,
+    scoring: &str,
+    rows: &[(String, String)],
+) {
+    // the scoring system sits in the gap above the panel
+    let (name_params, name_dims) = text_config.params_and_dims(&scoring.to_string(), 0.75);
     draw_text_ex(
-        &format!("{:0>6}", score.val),
-        x,
-        score_head_y + 1.0 * scl,
-        base_params,
+        scoring,
+        (GAME_WIDTH - 5.0) * scl - name_dims.width / 2.0,
+        15.85 * scl,
+        name_params,
     );
 
+    // Everything is measured relative to the first heading
+    let (base_params, base_dimensions) = text_config.params_and_dims(&rows[0].0, 1.25);
+    let x = (GAME_WIDTH - 6.0 - base_dimensions.width / 2.0 / scl) * scl;
+    for (i, (head, body)) in rows.iter().enumerate() {
+        let head_y = (GAME_HEIGHT - 12.0 + 2.5 * i as f32) * scl;
+        draw_text_ex(head, x, head_y, base_params);
+        draw_text_ex(body, x, head_y + 1.0 * scl, base_params);
+    }
+
     let border_pos = vec2(GAME_WIDTH - 8.5, 17.0);
     draw_border(textures, scl, border_pos, 7.0, 8.0);
 
@@ -338,6 +390,15 @@ fn draw_score(textures: &Texture2D, text_config: &TextParamsConfig, scl: f32, sc
             },
         );
         draw_text_ex("GAME OVER", go_x, go_y, game_over_params);
+
+        let results_text = &"PUSH START".to_string();
+        let (results_params, results_dims) = text_config.params_and_dims(results_text, 1.25);
+        draw_text_ex(
+            results_text,
+            (GAME_WIDTH / 2.0) * scl - results_dims.width / 2.0,
+            go_y + 2.0 * scl,
+            results_params,
+        );
     }
 }
 
@@ -471,6 +532,31 @@ fn draw_score_popup(text_config: &TextParamsConfig, scl: f32, well_pos: &Vec2, s
     draw_text_ex(&score_text, x, y, params);
 }
 
+// room for every callout of a single lock, the last ending above the screen edge
+const CALLOUT_ROWS: usize = 4;
+
+// newest callouts first, beside the well under the score panel
+fn draw_callouts(text_config: &TextParamsConfig, scl: f32, well_pos: &Vec2, callouts: &[Callout]) {
+    for (i, callout) in callouts.iter().rev().take(CALLOUT_ROWS).enumerate() {
+        let (params, _) = text_config.params_and_dims(&callout.text, 1.0);
+        let x = (well_pos.x + WELL_WIDTH as f32 + 1.5) * scl;
+        let y = (well_pos.y + WELL_HEIGHT as f32 + 0.9 + 0.9 * i as f32) * scl;
+
+        draw_text_ex(
+            &callout.text,
+            x + 0.1 * scl,
+            y + 0.1 * scl,
+            TextParams {
+                font_size: params.font_size,
+                font: params.font,
+                color: DARK,
+                ..Default::default()
+            },
+        );
+        draw_text_ex(&callout.text, x, y, params);
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct TextParamsConfig {
     pub base: TextParams,
@@ -503,11 +589,270 @@ fn draw_title(gs: &GameState, text_config: &TextParamsConfig) {
     let y = (GAME_HEIGHT / 3.0) * gs.scl;
     draw_text_ex(title, x, y, title_params);
 
-    let start_text = &"PUSH START".to_string();
-    let (start_params, start_dims) = text_config.params_and_dims(start_text, 2.0);
-    let x = (GAME_WIDTH / 4.0) * gs.scl - start_dims.width / 2.0;
-    let y = (GAME_HEIGHT - GAME_HEIGHT / 3.0) * gs.scl;
-    draw_text_ex(&start_text, x, y, start_params);
+    let y = GAME_HEIGHT - GAME_HEIGHT / 3.0;
+    for (i, text) in ["PUSH START", "CONTROLS"].iter().enumerate() {
+        draw_menu_item(
+            text_config,
+            gs.scl,
+            &text.to_string(),
+            y + 2.0 * i as f32,
+            gs.menu_cursor == i,
+        );
+    }
+}
+
+// rows shown at once, the list scrolls with the cursor
+const CONTROLS_ROWS: usize = 12;
+
+fn draw_controls(gs: &GameState, text_config: &TextParamsConfig) {
+    draw_heading(text_config, gs.scl, "CONTROLS");
+    let bindings = &gs.options.bindings;
+    let conflicts = bindings.conflicts();
+    let button_conflicts = bindings.button_conflicts();
+    // actions, then handling, then the defaults
+    let items = handling_items();
+    let rows = Action::ALL.len() + items.len() + 1;
+    let first = usize::min(
+        gs.menu_cursor.saturating_sub(CONTROLS_ROWS / 2),
+        rows.saturating_sub(CONTROLS_ROWS),
+    );
+    for (i, row) in (first..rows).take(CONTROLS_ROWS).enumerate() {
+        let text = match (Action::ALL.get(row), row.checked_sub(Action::ALL.len())) {
+            (Some(action), _) => {
+                let keys = bindings.keys(*action);
+                let buttons = bindings.buttons(*action);
+                let text = if gs.binding && gs.menu_cursor == row {
+                    "PRESS A KEY".to_string()
+                } else {
+                    let names: Vec<String> = keys
+                        .iter()
+                        .map(|k| controls::key_name(*k))
+                        .chain(buttons.iter().map(|b| b.name().to_string()))
+                        .collect();
+                    names.join(" ")
+                };
+                // a key or button bound to two actions is marked on both
+                let mark = if keys.iter().any(|k| conflicts.contains(k))
+                    || buttons.iter().any(|b| button_conflicts.contains(b))
+                {
+                    "!"
+                } else {
+                    " "
+                };
+                format!("{}{:<11}{:>12}", mark, action.name(), text)
+            }
+            (None, Some(idx)) if idx < items.len() => item_text(items[idx], gs),
+            _ => "DEFAULTS".to_string(),
+        };
+        draw_menu_item(
+            text_config,
+            gs.scl,
+            &text,
+            8.0 + 1.5 * i as f32,
+            gs.menu_cursor == row,
+        );
+    }
+
+    let conflict = conflicts
+        .first()
+        .map(|k| controls::key_name(*k))
+        .or(button_conflicts.first().map(|b| b.name().to_string()));
+    let help = &match conflict {
+        Some(name) => format!("! {} IS BOUND TWICE", name),
+        None => "ENTER ADDS A KEY, DELETE CLEARS, ESC SAVES".to_string(),
+    };
+    let (params, dims) = text_config.params_and_dims(help, 1.0);
+    draw_text_ex(
+        help,
+        (GAME_WIDTH / 2.0) * gs.scl - dims.width / 2.0,
+        (GAME_HEIGHT - 2.0) * gs.scl,
+        params,
+    );
+}
+
+fn draw_heading(text_config: &TextParamsConfig, scl: f32, text: &str) {
+    let heading = &text.to_string();
+    let (heading_params, heading_dims) = text_config.params_and_dims(heading, 3.0);
+    draw_text_ex(
+        heading,
+        (GAME_WIDTH / 2.0) * scl - heading_dims.width / 2.0,
+        6.0 * scl,
+        heading_params,
+    );
+}
+
+const GAME_TYPE_ROWS: usize = 10;
+
+fn draw_game_type_menu(gs: &GameState, text_config: &TextParamsConfig) {
+    draw_heading(text_config, gs.scl, "GAME TYPE");
+    // custom modes make the list scroll with the cursor
+    let game_types = modes::game_types(gs);
+    let first = usize::min(
+        gs.menu_cursor.saturating_sub(GAME_TYPE_ROWS / 2),
+        game_types.len().saturating_sub(GAME_TYPE_ROWS),
+    );
+    for (i, game_type) in game_types
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(GAME_TYPE_ROWS)
+    {
+        draw_menu_item(
+            text_config,
+            gs.scl,
+            &modes::game_type_name(gs, *game_type),
+            9.0 + 2.0 * (i - first) as f32,
+            i == gs.menu_cursor,
+        );
+    }
+
+    let description = &modes::game_type_description(gs, gs.options.game_type);
+    let (params, dims) = text_config.params_and_dims(description, 1.0);
+    draw_text_ex(
+        description,
+        (GAME_WIDTH / 2.0) * gs.scl - dims.width / 2.0,
+        (GAME_HEIGHT - 2.0) * gs.scl,
+        params,
+    );
+}
+
+fn draw_level_menu(gs: &GameState, text_config: &TextParamsConfig) {
+    let scl = gs.scl;
+    draw_heading(
+        text_config,
+        scl,
+        &modes::game_type_name(gs, gs.options.game_type),
+    );
+
+    let cell = 2.0;
+    let x = GAME_WIDTH / 2.0 - cell * 5.0;
+    let y = 8.0;
+    let grid_rows = if gs.options.game_type.has_level() {
+        2
+    } else {
+        0
+    };
+    for level in 0..(grid_rows * 10) {
+        let cx = x + (level % 10) as f32 * cell;
+        let cy = y + (level / 10) as f32 * cell;
+        let text = &format!("{}", level);
+        let (mut params, dims) = text_config.params_and_dims(text, 1.25);
+        if level == gs.options.start_level {
+            draw_rectangle(cx * scl, cy * scl, cell * scl, cell * scl, LIGHT);
+            params.color = DARK;
+        }
+        draw_text_ex(
+            text,
+            (cx + cell / 2.0) * scl - dims.width / 2.0,
+            (cy + cell / 2.0) * scl + dims.height / 2.0,
+            params,
+        );
+    }
+    if grid_rows > 0 {
+        draw_border(&gs.textures, scl, vec2(x, y), cell * 10.0, cell * 2.0);
+    }
+
+    for (i, item) in option_items(gs).iter().enumerate() {
+        draw_menu_item(
+            text_config,
+            scl,
+            &item_text(*item, gs),
+            y + cell * grid_rows as f32 + 3.0 + 2.0 * i as f32,
+            gs.menu_cursor == grid_rows + i,
+        );
+    }
+}
+
+fn draw_menu_item(text_config: &TextParamsConfig, scl: f32, text: &String, y: f32, selected: bool) {
+    let (mut params, dims) = text_config.params_and_dims(text, 1.25);
+    let x = (GAME_WIDTH / 2.0) * scl - dims.width / 2.0;
+    if selected {
+        draw_rectangle(
+            x - 0.5 * scl,
+            y * scl - dims.height - 0.5 * scl,
+            dims.width + 1.0 * scl,
+            dims.height + 1.0 * scl,
+            LIGHT,
+        );
+        params.color = DARK;
+    }
+    draw_text_ex(text, x, y * scl, params);
+}
+
+fn draw_ending(gs: &GameState, text_config: &TextParamsConfig) {
+    // the final stack shows behind the summary, fading and invisible ones too
+    let offset = well_offset();
+    draw_well(offset, gs.scl);
+    draw_placed(gs, offset);
+    draw_rectangle(
+        0.0,
+        0.0,
+        GAME_WIDTH * gs.scl,
+        GAME_HEIGHT * gs.scl,
+        Color { a: 0.6, ..DARK },
+    );
+
+    draw_heading(text_config, gs.scl, modes::ending_heading(gs));
+    for (i, text) in modes::summary(gs).iter().enumerate() {
+        let (params, dims) = text_config.params_and_dims(text, 1.5);
+        draw_text_ex(
+            text,
+            (GAME_WIDTH / 2.0) * gs.scl - dims.width / 2.0,
+            (10.0 + 2.0 * i as f32) * gs.scl,
+            params,
+        );
+    }
+
+    if modes::can_continue(gs) {
+        let continue_text = &"CONTINUE ENDLESS".to_string();
+        draw_menu_item(
+            text_config,
+            gs.scl,
+            continue_text,
+            GAME_HEIGHT - 5.0,
+            gs.menu_cursor == 0,
+        );
+        draw_menu_item(
+            text_config,
+            gs.scl,
+            &"QUIT".to_string(),
+            GAME_HEIGHT - 3.0,
+            gs.menu_cursor == 1,
+        );
+        return;
+    }
+
+    let continue_text = &"PUSH START".to_string();
+    let (params, dims) = text_config.params_and_dims(continue_text, 1.25);
+    draw_text_ex(
+        continue_text,
+        (GAME_WIDTH / 2.0) * gs.scl - dims.width / 2.0,
+        (GAME_HEIGHT - 3.0) * gs.scl,
+        params,
+    );
+}
+
+fn draw_high_scores(gs: &GameState, text_config: &TextParamsConfig) {
+    draw_heading(text_config, gs.scl, "HIGH SCORES");
+    let table = &modes::high_score_key(gs).replace('_', " ").to_uppercase();
+    let (params, dims) = text_config.params_and_dims(table, 1.0);
+    draw_text_ex(
+        table,
+        (GAME_WIDTH / 2.0) * gs.scl - dims.width / 2.0,
+        8.0 * gs.scl,
+        params,
+    );
+
+    for (i, score) in gs.high_scores.iter().enumerate() {
+        let text = &format!("{:>2}. {:0>7}", i + 1, score);
+        draw_menu_item(
+            text_config,
+            gs.scl,
+            text,
+            10.5 + 1.75 * i as f32,
+            gs.score.rank == Some(i),
+        );
+    }
 }
 
 pub fn draw(gs: &GameState) {
@@ -524,16 +869,24 @@ pub fn draw(gs: &GameState) {
 
     match gs.game_mode {
         GameMode::Title => draw_title(gs, &text_config),
-        GameMode::Play | GameMode::Pause => draw_play(gs, &text_config),
-        _ => {}
+        GameMode::GameTypeMenu => draw_game_type_menu(gs, &text_config),
+        GameMode::Ending => draw_ending(gs, &text_config),
+        GameMode::HighScore => draw_high_scores(gs, &text_config),
+        GameMode::LevelAndHeightMenu => draw_level_menu(gs, &text_config),
+        GameMode::Play | GameMode::Pause | GameMode::Demo => draw_play(gs, &text_config),
+        GameMode::Controls => draw_controls(gs, &text_config),
     }
 }
 
-pub fn draw_play(gs: &GameState, text_config: &TextParamsConfig) {
-    let offset = vec2(
+fn well_offset() -> Vec2 {
+    vec2(
         GAME_WIDTH / 2.0 - WELL_WIDTH as f32 / 2.0,
         GAME_HEIGHT / 2.0 - WELL_HEIGHT as f32 / 2.0,
-    );
+    )
+}
+
+pub fn draw_play(gs: &GameState, text_config: &TextParamsConfig) {
+    let offset = well_offset();
 
     // information stuff
     draw_statistics(
@@ -542,6 +895,7 @@ pub fn draw_play(gs: &GameState, text_config: &TextParamsConfig) {
         gs.scl,
         &gs.tetrominos,
         &gs.statistics,
+        &gs.score,
     );
 
     if gs.debug {
@@ -552,6 +906,18 @@ pub fn draw_play(gs: &GameState, text_config: &TextParamsConfig) {
             1.25 * gs.scl,
             BLUE,
         );
+        draw_text(
+            format!(
+                "{} GRAVITY {:.2} F/ROW",
+                modes::gravity_curve(gs).name(),
+                modes::frames_per_row(gs)
+            )
+            .as_str(),
+            offset.x * gs.scl,
+            2.0 * gs.scl,
+            1.25 * gs.scl,
+            BLUE,
+        );
     }
     draw_well(offset, gs.scl);
     draw_border(
@@ -592,21 +958,45 @@ pub fn draw_play(gs: &GameState, text_config: &TextParamsConfig) {
                 + 10.0,
             params3,
         );
+        if gs.options.game_type == GameType::Zen {
+            let text4 = &"\"ESC\" TO FINISH".to_string();
+            let (params4, dims4) = text_config.params_and_dims(text4, 1.0);
+            draw_text_ex(
+                text4,
+                (offset.x + WELL_WIDTH as f32 / 2.0) * gs.scl - dims4.width / 2.0,
+                (offset.y + WELL_HEIGHT as f32 / 3.0) * gs.scl - dims4.height / 2.0
+                    + dims1.height
+                    + dims2.height
+                    + dims3.height
+                    + 20.0,
+                params4,
+            );
+        }
         draw_border(&gs.textures, gs.scl, vec2(2.0, 1.0), 4.0, 4.0);
         draw_border(&gs.textures, gs.scl, vec2(GAME_WIDTH - 8.0, 1.0), 5.0, 13.0);
         return; // not giving the player an advantage by seeing the well
     }
+    if gs.game_mode == GameMode::Demo {
+        let text = &"DEMO".to_string();
+        let (params, dims) = text_config.params_and_dims(text, 2.0);
+        draw_text_ex(
+            text,
+            (offset.x + WELL_WIDTH as f32 / 2.0) * gs.scl - dims.width / 2.0,
+            (offset.y + WELL_HEIGHT as f32 / 3.0) * gs.scl - dims.height / 2.0,
+            params,
+        );
+    }
     draw_hold(&gs.textures, &text_config, gs.scl, &gs.hold);
     draw_next(&gs.textures, &text_config, gs.scl, &gs.next);
 
     // game stuff
-    draw_placed(&gs.textures, offset, gs.scl, &gs.placed_blocks, &gs.debug);
+    draw_placed(gs, offset);
 
     let entered = match &gs.line_clear {
         Some(line_clear) => {
-            gs.current.entry_timer >= ENTRY_DELAY && line_clear.counter >= LINE_CLEAR_DELAY
+            gs.current.entry_timer >= gs.delays.entry && line_clear.counter >= gs.delays.line_clear
         }
-        None => gs.current.entry_timer >= ENTRY_DELAY,
+        None => gs.current.entry_timer >= gs.delays.entry,
     };
 
     if entered {
@@ -640,23 +1030,35 @@ pub fn draw_play(gs: &GameState, text_config: &TextParamsConfig) {
         );
     }
 
-    draw_score(&gs.textures, &text_config, gs.scl, &gs.score);
+    draw_score(
+        &gs.textures,
+        &text_config,
+        gs.scl,
+        &gs.score,
+        gs.scoring.name(),
+        &modes::hud(gs),
+    );
     if gs.last_score.val > 0 && gs.last_score.creation < SCORE_TIMEOUT {
         draw_score_popup(&text_config, gs.scl, &offset, &gs.last_score);
     }
+    draw_callouts(text_config, gs.scl, &offset, &gs.callouts);
 
     match &gs.line_clear {
         Some(line_clear) => {
-            draw_rectangle(
-                offset.x * gs.scl,
-                (offset.y + line_clear.y_pos as f32) * gs.scl,
-                f32::min(
-                    WELL_WIDTH as f32,
-                    WELL_WIDTH as f32 * line_clear.counter as f32 * 1.5 / LINE_CLEAR_DELAY as f32,
-                ) * gs.scl,
-                line_clear.lines.len() as f32 * gs.scl,
-                DARK,
+            // cleared rows need not be next to each other
+            let width = f32::min(
+                WELL_WIDTH as f32,
+                WELL_WIDTH as f32 * line_clear.counter as f32 * 1.5 / gs.delays.line_clear as f32,
             );
+            for y in line_clear.lines.iter() {
+                draw_rectangle(
+                    offset.x * gs.scl,
+                    (offset.y + *y as f32) * gs.scl,
+                    width * gs.scl,
+                    gs.scl,
+                    DARK,
+                );
+            }
         }
         None => {}
     }
@@ -0,0 +1,87 @@
+/*
+ Finesse
+ The fewest inputs that bring a freshly spawned tetromino to where a piece was placed,
+ searched on an empty well like finesse trainers do.
+*/
+use std::collections::{HashSet, VecDeque};
+
+use super::{
+    collision::can_translate_horizontally,
+    components::{Ghost, WELL_HEIGHT, WELL_WIDTH},
+    srs, Block, Tetromino,
+};
+use macroquad::prelude::vec2;
+
+// columns and rows covered by the piece, rows relative to its top
+fn footprint(tetromino: &Tetromino) -> Vec<(i32, i32)> {
+    let points = tetromino.relative_points(&tetromino.pos);
+    let top = points.iter().map(|p| p.y as i32).min().unwrap_or(0);
+    let mut footprint: Vec<(i32, i32)> = points
+        .iter()
+        .map(|p| (p.x as i32, p.y as i32 - top))
+        .collect();
+    footprint.sort();
+
+    footprint
+}
+
+fn shift(tetromino: &Tetromino, placed: &Vec<Option<Block>>, dx: f32) -> Option<Tetromino> {
+    let new_pos = vec2(tetromino.pos.x + dx, tetromino.pos.y);
+    if can_translate_horizontally(tetromino, placed, &new_pos) {
+        let mut moved = *tetromino;
+        moved.pos = new_pos;
+        return Some(moved);
+    }
+
+    None
+}
+
+// holding a direction until the wall counts as a single input
+fn shift_to_wall(tetromino: &Tetromino, placed: &Vec<Option<Block>>, dx: f32) -> Tetromino {
+    let mut moved = *tetromino;
+    while let Some(next) = shift(&moved, placed, dx) {
+        moved = next;
+    }
+
+    moved
+}
+
+pub fn minimal_inputs(spawned: &Tetromino, placed_piece: &Tetromino) -> usize {
+    let empty: Vec<Option<Block>> = vec![None; WELL_WIDTH * WELL_HEIGHT];
+    let target = footprint(placed_piece);
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert(footprint(spawned));
+    queue.push_back((*spawned, 0));
+
+    while let Some((tetromino, inputs)) = queue.pop_front() {
+        if footprint(&tetromino) == target {
+            return inputs;
+        }
+
+        let mut next = vec![
+            shift_to_wall(&tetromino, &empty, -1.0),
+            shift_to_wall(&tetromino, &empty, 1.0),
+        ];
+        next.extend(shift(&tetromino, &empty, -1.0));
+        next.extend(shift(&tetromino, &empty, 1.0));
+        for clockwise in [true, false] {
+            let mut rotated = tetromino;
+            let mut ghost = Ghost {
+                pos: rotated.pos,
+                dirty: false,
+            };
+            srs::rotate(clockwise, &mut rotated, &empty, &mut ghost);
+            next.push(rotated);
+        }
+
+        for t in next {
+            if seen.insert(footprint(&t)) {
+                queue.push_back((t, inputs + 1));
+            }
+        }
+    }
+
+    // the piece was tucked or spun somewhere the empty well cannot reach
+    0
+}
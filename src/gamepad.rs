@@ -0,0 +1,184 @@
+/*
+ Gamepad
+ Buttons from every connected pad, read once a frame so actions and menus can
+ ask about them the way they ask about keys. The left stick counts as the
+ d-pad once it leaves the deadzone. The web build sees no pads.
+*/
+use std::cell::RefCell;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Button {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl Button {
+    pub const ALL: [Button; 16] = [
+        Button::South,
+        Button::East,
+        Button::West,
+        Button::North,
+        Button::LeftBumper,
+        Button::RightBumper,
+        Button::LeftTrigger,
+        Button::RightTrigger,
+        Button::Select,
+        Button::Start,
+        Button::LeftStick,
+        Button::RightStick,
+        Button::DPadUp,
+        Button::DPadDown,
+        Button::DPadLeft,
+        Button::DPadRight,
+    ];
+
+    // face buttons by position, so the same on every layout
+    pub fn name(self) -> &'static str {
+        match self {
+            Button::South => "SOUTH",
+            Button::East => "EAST",
+            Button::West => "WEST",
+            Button::North => "NORTH",
+            Button::LeftBumper => "LB",
+            Button::RightBumper => "RB",
+            Button::LeftTrigger => "LT",
+            Button::RightTrigger => "RT",
+            Button::Select => "SELECT",
+            Button::Start => "START",
+            Button::LeftStick => "L3",
+            Button::RightStick => "R3",
+            Button::DPadUp => "D-UP",
+            Button::DPadDown => "D-DOWN",
+            Button::DPadLeft => "D-LEFT",
+            Button::DPadRight => "D-RIGHT",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Button> {
+        Button::ALL
+            .iter()
+            .find(|b| b.name().eq_ignore_ascii_case(name))
+            .copied()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn gilrs(self) -> gilrs::Button {
+        match self {
+            Button::South => gilrs::Button::South,
+            Button::East => gilrs::Button::East,
+            Button::West => gilrs::Button::West,
+            Button::North => gilrs::Button::North,
+            Button::LeftBumper => gilrs::Button::LeftTrigger,
+            Button::RightBumper => gilrs::Button::RightTrigger,
+            Button::LeftTrigger => gilrs::Button::LeftTrigger2,
+            Button::RightTrigger => gilrs::Button::RightTrigger2,
+            Button::Select => gilrs::Button::Select,
+            Button::Start => gilrs::Button::Start,
+            Button::LeftStick => gilrs::Button::LeftThumb,
+            Button::RightStick => gilrs::Button::RightThumb,
+            Button::DPadUp => gilrs::Button::DPadUp,
+            Button::DPadDown => gilrs::Button::DPadDown,
+            Button::DPadLeft => gilrs::Button::DPadLeft,
+            Button::DPadRight => gilrs::Button::DPadRight,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Pads {
+    #[cfg(not(target_arch = "wasm32"))]
+    gilrs: Option<gilrs::Gilrs>,
+    down: Vec<Button>,
+    // down on the frame before
+    last: Vec<Button>,
+}
+
+thread_local! {
+    static PADS: RefCell<Pads> = RefCell::new(Pads::default());
+}
+
+pub fn init() {
+    #[cfg(not(target_arch = "wasm32"))]
+    PADS.with(|pads| pads.borrow_mut().gilrs = gilrs::Gilrs::new().ok());
+}
+
+// once a frame before input
+pub fn update(deadzone: f32) {
+    PADS.with(|pads| {
+        let mut pads = pads.borrow_mut();
+        let down = read(&mut pads, deadzone);
+        pads.last = std::mem::replace(&mut pads.down, down);
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(pads: &mut Pads, deadzone: f32) -> Vec<Button> {
+    let mut down = Vec::new();
+    let gilrs = match &mut pads.gilrs {
+        Some(gilrs) => gilrs,
+        None => return down,
+    };
+    // events keep each pad's state current
+    while gilrs.next_event().is_some() {}
+
+    for (_, pad) in gilrs.gamepads() {
+        let x = pad.value(gilrs::Axis::LeftStickX);
+        let y = pad.value(gilrs::Axis::LeftStickY);
+        let stick = [
+            (Button::DPadUp, y > deadzone),
+            (Button::DPadDown, y < -deadzone),
+            (Button::DPadLeft, x < -deadzone),
+            (Button::DPadRight, x > deadzone),
+        ];
+        for button in Button::ALL {
+            let held = pad.is_pressed(button.gilrs())
+                || stick.iter().any(|(b, tilted)| *b == button && *tilted);
+            if held && !down.contains(&button) {
+                down.push(button);
+            }
+        }
+    }
+
+    down
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(_pads: &mut Pads, _deadzone: f32) -> Vec<Button> {
+    Vec::new()
+}
+
+pub fn is_button_pressed(button: Button) -> bool {
+    PADS.with(|pads| {
+        let pads = pads.borrow();
+        pads.down.contains(&button) && !pads.last.contains(&button)
+    })
+}
+
+pub fn is_button_down(button: Button) -> bool {
+    PADS.with(|pads| pads.borrow().down.contains(&button))
+}
+
+pub fn is_button_released(button: Button) -> bool {
+    PADS.with(|pads| {
+        let pads = pads.borrow();
+        !pads.down.contains(&button) && pads.last.contains(&button)
+    })
+}
+
+pub fn get_last_button_pressed() -> Option<Button> {
+    Button::ALL.into_iter().find(|b| is_button_pressed(*b))
+}
@@ -12,29 +12,239 @@ pub fn move_downwards(gs: &mut GameState) {
 
     gs.current.pos = new_pos;
     gs.gravity.meter = 0.0;
+    if gs.gravity.soft_drop {
+        gs.current.soft_dropped += 1;
+    }
     if gs.current.locking {
         gs.current.lock_counter = 0;
     }
 }
 
+// every row drops once for each removed line below it, the removed lines may be apart
 pub fn apply_gravity(placed: &mut Vec<Option<Block>>, removed_lines: &Vec<usize>) {
-    let y_delta = removed_lines.len();
-    debug!("removed lines {}", y_delta);
-    let line = removed_lines.iter().min().unwrap();
-    debug!("apply gravity above (below) line {}", line);
-
-    for y in (0..*line).rev() {
+    debug!("removed lines {:?}", removed_lines);
+    let mut target = WELL_HEIGHT;
+    for y in (0..WELL_HEIGHT).rev() {
+        if removed_lines.contains(&y) {
+            continue;
+        }
+        target -= 1;
+        if target == y {
+            continue;
+        }
+        debug!("moving row {} to {}", y, target);
         for x in 0..WELL_WIDTH {
-            let idx = xy_idx(x as f32, y as f32);
-            let new_idx = xy_idx(x as f32, (y + y_delta) as f32);
-            match placed[idx] {
-                Some(b) => {
-                    debug!("Moving {}, {} to {}, {}", x, y, x, (y + y_delta));
-                    placed[new_idx] = Some(b);
-                    placed[idx] = None;
+            placed[xy_idx(x as f32, target as f32)] = placed[xy_idx(x as f32, y as f32)].take();
+        }
+    }
+}
+
+// blocks connected to each other, only through blocks of the same piece if asked
+fn block_groups(placed: &[Option<Block>], same_piece: bool) -> Vec<Vec<usize>> {
+    let mut seen = vec![false; placed.len()];
+    let mut groups = Vec::new();
+    for start in 0..placed.len() {
+        let piece = match placed[start] {
+            Some(block) if !seen[start] => block.piece,
+            _ => continue,
+        };
+        seen[start] = true;
+        let mut group = vec![start];
+        let mut i = 0;
+        while i < group.len() {
+            let idx = group[i];
+            i += 1;
+            let (x, y) = (idx % WELL_WIDTH, idx / WELL_WIDTH);
+            let mut neighbours = Vec::new();
+            if x > 0 {
+                neighbours.push(idx - 1);
+            }
+            if x + 1 < WELL_WIDTH {
+                neighbours.push(idx + 1);
+            }
+            if y > 0 {
+                neighbours.push(idx - WELL_WIDTH);
+            }
+            if y + 1 < WELL_HEIGHT {
+                neighbours.push(idx + WELL_WIDTH);
+            }
+            for n in neighbours {
+                let joins = match placed[n] {
+                    Some(block) => !same_piece || block.piece == piece,
+                    None => false,
+                };
+                if joins && !seen[n] {
+                    seen[n] = true;
+                    group.push(n);
+                }
+            }
+        }
+        groups.push(group);
+    }
+
+    groups
+}
+
+fn can_fall(placed: &[Option<Block>], group: &[usize]) -> bool {
+    group.iter().all(|idx| {
+        let below = idx + WELL_WIDTH;
+        below < placed.len() && (placed[below].is_none() || group.contains(&below))
+    })
+}
+
+// lets groups of blocks fall a row at a time until none of them can
+pub fn apply_group_gravity(placed: &mut [Option<Block>], same_piece: bool) {
+    while let Some(group) = block_groups(placed, same_piece)
+        .into_iter()
+        .find(|g| can_fall(placed, g))
+    {
+        let blocks: Vec<Option<Block>> = group.iter().map(|idx| placed[*idx].take()).collect();
+        for (idx, block) in group.iter().zip(blocks) {
+            placed[idx + WELL_WIDTH] = block;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        collision::completed_lines, components::LIGHT, spawner::despawn_blocks, TetrominoType,
+    };
+
+    fn block(piece: usize) -> Option<Block> {
+        Some(Block {
+            color: LIGHT,
+            kind: TetrominoType::I,
+            garbage: false,
+            locked: 0,
+            piece,
+        })
+    }
+
+    // rows listed top to bottom sit on the floor, the piece id is the digit
+    fn board(rows: &[&str]) -> Vec<Option<Block>> {
+        let mut placed = vec![None; WELL_WIDTH * WELL_HEIGHT];
+        let top = WELL_HEIGHT - rows.len();
+        for (i, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if let Some(piece) = c.to_digit(10) {
+                    placed[xy_idx(x as f32, (top + i) as f32)] = block(piece as usize);
                 }
-                _ => continue,
             }
         }
+
+        placed
+    }
+
+    fn pieces(placed: &[Option<Block>]) -> Vec<Option<usize>> {
+        placed.iter().map(|b| b.map(|b| b.piece)).collect()
+    }
+
+    fn clear(placed: &mut Vec<Option<Block>>, lines: &Vec<usize>) {
+        despawn_blocks(placed, lines);
+        apply_gravity(placed, lines);
+    }
+
+    #[test]
+    fn split_double() {
+        let mut placed = board(&[
+            "1.........",
+            "2222222222",
+            "3.........",
+            "4444444444",
+            "5.........",
+        ]);
+        let lines = vec![WELL_HEIGHT - 4, WELL_HEIGHT - 2];
+        clear(&mut placed, &lines);
+
+        assert_eq!(
+            pieces(&placed),
+            pieces(&board(&["1.........", "3.........", "5........."]))
+        );
+    }
+
+    #[test]
+    fn split_triple() {
+        let mut placed = board(&[
+            "1.........",
+            "2222222222",
+            "2222222222",
+            "3.........",
+            "4444444444",
+            "5.........",
+        ]);
+        let lines = vec![WELL_HEIGHT - 5, WELL_HEIGHT - 4, WELL_HEIGHT - 2];
+        clear(&mut placed, &lines);
+
+        assert_eq!(
+            pieces(&placed),
+            pieces(&board(&["1.........", "3.........", "5........."]))
+        );
+    }
+
+    #[test]
+    fn split_triple_one_row_apart() {
+        let mut placed = board(&[
+            "1.........",
+            "2222222222",
+            "3.........",
+            "4444444444",
+            "5.........",
+            "6666666666",
+        ]);
+        let lines = vec![WELL_HEIGHT - 5, WELL_HEIGHT - 3, WELL_HEIGHT - 1];
+        clear(&mut placed, &lines);
+
+        assert_eq!(
+            pieces(&placed),
+            pieces(&board(&["1.........", "3.........", "5........."]))
+        );
+    }
+
+    fn clear_grouped(placed: &mut Vec<Option<Block>>, lines: &Vec<usize>, same_piece: bool) {
+        despawn_blocks(placed, lines);
+        apply_group_gravity(placed, same_piece);
+    }
+
+    #[test]
+    fn sticky_groups_fall_together() {
+        let mut placed = board(&["7.........", "78........", "9999999999", "5........."]);
+        clear_grouped(&mut placed, &vec![WELL_HEIGHT - 2], false);
+
+        assert_eq!(
+            pieces(&placed),
+            pieces(&board(&["7.........", "78........", "5........."]))
+        );
+    }
+
+    #[test]
+    fn sticky_group_lands_on_any_block() {
+        let mut placed = board(&["11........", "2222222222", "3.........", "3.....4..."]);
+        clear_grouped(&mut placed, &vec![WELL_HEIGHT - 3], false);
+
+        assert_eq!(
+            pieces(&placed),
+            pieces(&board(&["11........", "3.........", "3.....4..."]))
+        );
+    }
+
+    #[test]
+    fn cascade_pieces_fall_apart() {
+        let mut placed = board(&["7.........", "78........", "9999999999", "5........."]);
+        clear_grouped(&mut placed, &vec![WELL_HEIGHT - 2], true);
+
+        assert_eq!(
+            pieces(&placed),
+            pieces(&board(&["7.........", "7.........", "58........"]))
+        );
+    }
+
+    #[test]
+    fn cascade_fills_a_line() {
+        let mut placed = board(&[".3........", "2222222222", "4.44444444"]);
+        clear_grouped(&mut placed, &vec![WELL_HEIGHT - 2], true);
+
+        assert_eq!(completed_lines(&placed), vec![WELL_HEIGHT - 1]);
     }
 }
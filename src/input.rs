@@ -1,18 +1,21 @@
 use crate::{
     components::{
-        get_game_state, get_level_gravity_max, FrameInput, GameMode, Ghost, AUTO_SHIFT_DELAY,
-        HARD_DROP_GRAVITY, LOCK_DELAY, SOFT_DROP_GRAVITY,
+        reset_game_state, FrameInput, GameMode, GameType, Ghost, Options, HARD_DROP_GRAVITY,
+        LOCK_DELAY,
     },
+    config,
+    controls::{self, Action, Bindings},
+    demo,
+    gamepad::{get_last_button_pressed, is_button_pressed, Button},
+    menu::{adjust_item, handling_items, option_items, MenuItem},
+    modes::{self, can_continue},
     spawner::{drain_next, reset_transform},
 };
 
-use super::{
-    collision::can_translate_horizontally, srs, Block, GameState, Tetromino, AUTO_SHIFT_TIMEOUT,
-    WELL_WIDTH,
-};
-use macroquad::prelude::{get_time, is_key_down, is_key_pressed, is_key_released, vec2, KeyCode};
+use super::{collision::can_translate_horizontally, srs, Block, GameState, Tetromino, WELL_WIDTH};
+use macroquad::prelude::{get_last_key_pressed, is_key_pressed, vec2, KeyCode};
 
-fn move_left(tetromino: &mut Tetromino, placed: &Vec<Option<Block>>, ghost: &mut Ghost) {
+pub fn move_left(tetromino: &mut Tetromino, placed: &Vec<Option<Block>>, ghost: &mut Ghost) {
     let new_pos = vec2(tetromino.pos.x - 1.0, tetromino.pos.y);
     if can_translate_horizontally(&tetromino, placed, &new_pos) {
         tetromino.pos = new_pos;
@@ -24,7 +27,7 @@ fn move_left(tetromino: &mut Tetromino, placed: &Vec<Option<Block>>, ghost: &mut
     }
 }
 
-fn move_right(tetromino: &mut Tetromino, placed: &Vec<Option<Block>>, ghost: &mut Ghost) {
+pub fn move_right(tetromino: &mut Tetromino, placed: &Vec<Option<Block>>, ghost: &mut Ghost) {
     let new_pos = vec2(tetromino.pos.x + 1.0, tetromino.pos.y);
     if can_translate_horizontally(&tetromino, placed, &new_pos) {
         tetromino.pos = new_pos;
@@ -41,118 +44,390 @@ pub fn input(gs: &mut GameState) {
         GameMode::Play => play_input(gs),
         GameMode::Pause => pause_input(gs),
         GameMode::Title => title_input(gs),
-        _ => {}
+        GameMode::GameTypeMenu => game_type_menu_input(gs),
+        GameMode::Ending => ending_input(gs),
+        GameMode::HighScore => high_score_input(gs),
+        GameMode::LevelAndHeightMenu => level_menu_input(gs),
+        GameMode::Demo => demo_input(gs),
+        GameMode::Controls => controls_input(gs),
     }
 }
 
+// menus go through these so keys and pad buttons navigate the same way
+fn menu_up() -> bool {
+    is_key_pressed(KeyCode::Up) || is_button_pressed(Button::DPadUp)
+}
+
+fn menu_down() -> bool {
+    is_key_pressed(KeyCode::Down) || is_button_pressed(Button::DPadDown)
+}
+
+fn menu_left() -> bool {
+    is_key_pressed(KeyCode::Left) || is_button_pressed(Button::DPadLeft)
+}
+
+fn menu_right() -> bool {
+    is_key_pressed(KeyCode::Right) || is_button_pressed(Button::DPadRight)
+}
+
+fn menu_confirm() -> bool {
+    is_key_pressed(KeyCode::Space)
+        || is_key_pressed(KeyCode::Enter)
+        || is_button_pressed(Button::South)
+        || is_button_pressed(Button::Start)
+}
+
+fn menu_back() -> bool {
+    is_key_pressed(KeyCode::Escape)
+        || is_key_pressed(KeyCode::Backspace)
+        || is_button_pressed(Button::East)
+}
+
+fn any_pressed() -> bool {
+    get_last_key_pressed().is_some() || get_last_button_pressed().is_some()
+}
+
+// rows: start and controls
 fn title_input(gs: &mut GameState) {
-    if is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter) {
-        gs.game_mode = GameMode::Play;
+    if any_pressed() {
+        gs.idle = 0;
+    }
+    if menu_up() || menu_down() {
+        gs.menu_cursor = if gs.menu_cursor == 0 { 1 } else { 0 };
+    }
+    if menu_confirm() && gs.menu_cursor == 1 {
+        gs.menu_cursor = 0;
+        gs.game_mode = GameMode::Controls;
+    } else if menu_confirm() {
+        gs.menu_cursor = game_type_cursor(gs);
+        gs.game_mode = GameMode::GameTypeMenu;
+    }
+}
+
+// rows: one per action, the stick deadzone, then the defaults
+fn controls_input(gs: &mut GameState) {
+    if gs.binding {
+        // escape cancels
+        let action = Action::ALL[gs.menu_cursor];
+        if let Some(key) = get_last_key_pressed() {
+            if key != KeyCode::Escape && controls::can_bind(key) {
+                gs.options.bindings.bind(action, key);
+            }
+            gs.binding = false;
+        } else if let Some(button) = get_last_button_pressed() {
+            gs.options.bindings.bind_button(action, button);
+            gs.binding = false;
+        }
+        return;
+    }
+
+    let items = handling_items();
+    let defaults_row = Action::ALL.len() + items.len();
+    if menu_up() && gs.menu_cursor > 0 {
+        gs.menu_cursor -= 1;
+    }
+    if menu_down() && gs.menu_cursor < defaults_row {
+        gs.menu_cursor += 1;
+    }
+    let action = Action::ALL.get(gs.menu_cursor).copied();
+    if is_key_pressed(KeyCode::Delete) || is_button_pressed(Button::West) {
+        if let Some(action) = action {
+            gs.options.bindings.clear(action);
+        }
+    }
+    let item = gs
+        .menu_cursor
+        .checked_sub(Action::ALL.len())
+        .and_then(|i| items.get(i).copied());
+    if let Some(item) = item {
+        let right = menu_right();
+        if menu_left() || right {
+            adjust_item(item, &mut gs.options, right);
+        }
+    }
+    if menu_confirm() && gs.menu_cursor == defaults_row {
+        let defaults = Options::default();
+        gs.options.bindings = defaults.bindings;
+        gs.options.handling = defaults.handling;
+    } else if menu_confirm() && action.is_some() {
+        gs.binding = true;
+    }
+    if menu_back() {
+        config::save(config::OPTIONS_PATH, &gs.options.to_config(&gs.settings));
+        gs.menu_cursor = 1;
+        gs.game_mode = GameMode::Title;
+    }
+}
+
+// any key or button ends the demo
+fn demo_input(gs: &mut GameState) {
+    if any_pressed() {
+        demo::stop(gs);
+    }
+}
+
+// row of the chosen game type in the game type menu
+fn game_type_cursor(gs: &GameState) -> usize {
+    modes::game_types(gs)
+        .iter()
+        .position(|t| *t == gs.options.game_type)
+        .unwrap_or(0)
+}
+
+fn game_type_menu_input(gs: &mut GameState) {
+    let game_types = modes::game_types(gs);
+    if menu_up() && gs.menu_cursor > 0 {
+        gs.menu_cursor -= 1;
+    }
+    if menu_down() && gs.menu_cursor < game_types.len() - 1 {
+        gs.menu_cursor += 1;
+    }
+    gs.options.game_type = game_types[gs.menu_cursor];
+
+    if menu_back() {
+        gs.game_mode = GameMode::Title;
+    }
+    if menu_confirm() {
+        gs.menu_cursor = if gs.options.game_type.has_level() {
+            gs.options.start_level / 10
+        } else {
+            0
+        };
+        gs.game_mode = GameMode::LevelAndHeightMenu;
+    }
+}
+
+// rows: levels 0-9 and 10-19 when the game type has a level, then its option items
+fn level_menu_input(gs: &mut GameState) {
+    let grid_rows = if gs.options.game_type.has_level() {
+        2
+    } else {
+        0
+    };
+    let items = option_items(gs);
+    if menu_up() && gs.menu_cursor > 0 {
+        gs.menu_cursor -= 1;
+        if gs.menu_cursor < grid_rows {
+            gs.options.start_level = gs.menu_cursor * 10 + gs.options.start_level % 10;
+        }
+    }
+    if menu_down() && gs.menu_cursor + 1 < grid_rows + items.len() {
+        gs.menu_cursor += 1;
+        if gs.menu_cursor < grid_rows {
+            gs.options.start_level = gs.menu_cursor * 10 + gs.options.start_level % 10;
+        }
+    }
+
+    let left = menu_left();
+    let right = menu_right();
+    if gs.menu_cursor < grid_rows {
+        let column = gs.options.start_level % 10;
+        if left && column > 0 {
+            gs.options.start_level -= 1;
+        }
+        if right && column < 9 {
+            gs.options.start_level += 1;
+        }
+    } else if left || right {
+        adjust_item(items[gs.menu_cursor - grid_rows], &mut gs.options, right);
+    }
+
+    if menu_back() {
+        gs.menu_cursor = game_type_cursor(gs);
+        gs.game_mode = GameMode::GameTypeMenu;
+    }
+    if menu_confirm() {
+        if gs.options.game_type == GameType::Puzzle {
+            match items.get(gs.menu_cursor) {
+                Some(MenuItem::Mission(idx)) => gs.options.puzzle = *idx,
+                _ => return,
+            }
+        }
+        config::save(config::OPTIONS_PATH, &gs.options.to_config(&gs.settings));
+        reset_game_state(gs, GameMode::Play);
+    }
+}
+
+// rows: continue and quit when the marathon may go on
+fn ending_input(gs: &mut GameState) {
+    if can_continue(gs) {
+        if menu_up() || menu_down() {
+            gs.menu_cursor = if gs.menu_cursor == 0 { 1 } else { 0 };
+        }
+        if menu_confirm() && gs.menu_cursor == 0 {
+            gs.endless = true;
+            gs.game_mode = GameMode::Play;
+            return;
+        }
+    }
+    if menu_confirm() && !gs.high_scores.is_empty() {
+        gs.game_mode = GameMode::HighScore;
+    } else if gs.options.game_type == GameType::Puzzle && (menu_confirm() || menu_back()) {
+        // back to the mission list
+        reset_game_state(gs, GameMode::LevelAndHeightMenu);
+        gs.menu_cursor = gs.options.puzzle;
+    } else if menu_confirm() || menu_back() {
+        reset_game_state(gs, GameMode::GameTypeMenu);
+        gs.menu_cursor = game_type_cursor(gs);
+    }
+}
+
+fn high_score_input(gs: &mut GameState) {
+    if menu_confirm() || menu_back() {
+        reset_game_state(gs, GameMode::GameTypeMenu);
+        gs.menu_cursor = game_type_cursor(gs);
     }
 }
 
 fn pause_input(gs: &mut GameState) {
-    if is_key_pressed(KeyCode::P) {
+    if gs.options.bindings.pressed(Action::Pause) {
         gs.game_mode = GameMode::Play;
     }
+    // zen has no other way to finish
+    if gs.options.game_type == GameType::Zen && menu_back() {
+        gs.game_mode = GameMode::Ending;
+    }
+}
+
+fn shift(gs: &mut GameState, direction: Action) -> bool {
+    let x = gs.current.pos.x;
+    if direction == Action::MoveLeft {
+        move_left(&mut gs.current, &gs.placed_blocks, &mut gs.ghost);
+    } else {
+        move_right(&mut gs.current, &gs.placed_blocks, &mut gs.ghost);
+    }
+
+    gs.current.pos.x != x
+}
+
+// moves once on press, then after das frames every arr frames. A waiting piece
+// never shifts but a held direction charges, see Handling
+fn auto_shift(gs: &mut GameState, bindings: &Bindings) {
+    let left = bindings.down(Action::MoveLeft);
+    let right = bindings.down(Action::MoveRight);
+    // the newest press wins while both are held
+    let direction = if bindings.pressed(Action::MoveLeft) {
+        Some(Action::MoveLeft)
+    } else if bindings.pressed(Action::MoveRight) {
+        Some(Action::MoveRight)
+    } else {
+        match gs.key_info.auto_shift {
+            Some(Action::MoveLeft) if left => Some(Action::MoveLeft),
+            Some(Action::MoveRight) if right => Some(Action::MoveRight),
+            _ if left => Some(Action::MoveLeft),
+            _ if right => Some(Action::MoveRight),
+            _ => None,
+        }
+    };
+
+    let handling = gs.options.handling;
+    let waiting = gs.line_clear.is_some() || gs.current.entry_timer < gs.delays.entry;
+    if !waiting && gs.key_info.entered != Some(gs.spawns) {
+        gs.key_info.entered = Some(gs.spawns);
+        gs.key_info.das_cut = handling.das_cut;
+        gs.key_info.arr_timer = 0;
+        if !handling.das_charge {
+            gs.key_info.auto_shift = None;
+        }
+    }
+    // the cut runs from entry whether or not a direction is held
+    let cut = !waiting && gs.key_info.das_cut > 0;
+    if cut {
+        gs.key_info.das_cut -= 1;
+    }
+
+    let direction = match direction {
+        Some(direction) => direction,
+        None => {
+            gs.key_info.auto_shift = None;
+            return;
+        }
+    };
+    if gs.key_info.auto_shift != Some(direction) {
+        gs.key_info.auto_shift = Some(direction);
+        gs.key_info.das_timer = 0;
+        gs.key_info.arr_timer = 0;
+        if !waiting {
+            shift(gs, direction);
+            gs.last_input = FrameInput::Move;
+        }
+        return;
+    }
+
+    gs.key_info.das_timer += 1;
+    if waiting {
+        return;
+    }
+    gs.last_input = FrameInput::Move;
+    if cut || gs.key_info.das_timer < handling.das {
+        return;
+    }
+    if handling.arr == 0 {
+        while shift(gs, direction) {}
+    } else if gs.key_info.arr_timer == 0 {
+        shift(gs, direction);
+        gs.key_info.arr_timer = handling.arr - 1;
+    } else {
+        gs.key_info.arr_timer -= 1;
+    }
 }
 
 pub fn play_input(gs: &mut GameState) {
-    let time = get_time();
+    let bindings = gs.options.bindings.clone();
     gs.last_input = FrameInput::None;
 
-    if is_key_released(KeyCode::Left) {
-        gs.key_info.auto_shift = (None, time);
-        gs.key_info.auto_shift_start = 0.;
-    }
-    if is_key_down(KeyCode::Left) {
-        if gs.key_info.auto_shift_start == 0. {
-            gs.key_info.auto_shift_start = time;
-            move_left(&mut gs.current, &gs.placed_blocks, &mut gs.ghost);
-            gs.key_info.auto_shift = (Some(KeyCode::Left), time);
-        }
-
-        if time - gs.key_info.auto_shift_start > AUTO_SHIFT_DELAY {
-            let (key, last_move) = gs.key_info.auto_shift;
-            match key {
-                Some(k) => {
-                    if k == KeyCode::Left && time - last_move > AUTO_SHIFT_TIMEOUT {
-                        move_left(&mut gs.current, &gs.placed_blocks, &mut gs.ghost);
-                        gs.key_info.auto_shift.1 = time;
-                    } else if k == KeyCode::Right {
-                        move_left(&mut gs.current, &gs.placed_blocks, &mut gs.ghost);
-                        gs.key_info.auto_shift = (Some(KeyCode::Left), time);
-                    }
-                }
-                _ => {
-                    move_left(&mut gs.current, &gs.placed_blocks, &mut gs.ghost);
-                    gs.key_info.auto_shift = (Some(KeyCode::Left), time);
-                }
-            }
+    if gs.score.topout {
+        if menu_confirm() {
+            gs.game_mode = GameMode::Ending;
         }
-        gs.last_input = FrameInput::Move;
-    }
-
-    if is_key_released(KeyCode::Right) {
-        gs.key_info.auto_shift = (None, time);
-        gs.key_info.auto_shift_start = 0.;
-    }
-    if is_key_down(KeyCode::Right) {
-        if gs.key_info.auto_shift_start == 0. {
-            gs.key_info.auto_shift_start = time;
-            move_right(&mut gs.current, &gs.placed_blocks, &mut gs.ghost);
-            gs.key_info.auto_shift = (Some(KeyCode::Right), time);
-        }
-
-        if time - gs.key_info.auto_shift_start > AUTO_SHIFT_DELAY {
-            let (key, last_move) = gs.key_info.auto_shift;
-            match key {
-                Some(k) => {
-                    if k == KeyCode::Right && time - last_move > AUTO_SHIFT_TIMEOUT {
-                        move_right(&mut gs.current, &gs.placed_blocks, &mut gs.ghost);
-                        gs.key_info.auto_shift.1 = time;
-                    } else if k == KeyCode::Left {
-                        move_right(&mut gs.current, &gs.placed_blocks, &mut gs.ghost);
-                        gs.key_info.auto_shift = (Some(KeyCode::Right), time);
-                    }
-                }
-                _ => {
-                    move_right(&mut gs.current, &gs.placed_blocks, &mut gs.ghost);
-                    gs.key_info.auto_shift = (Some(KeyCode::Right), time);
-                }
-            }
+        if bindings.pressed(Action::Restart) {
+            reset_game_state(gs, GameMode::Play);
         }
-        gs.last_input = FrameInput::Move;
+        return;
+    }
+
+    auto_shift(gs, &bindings);
+
+    if bindings.pressed(Action::MoveLeft) || bindings.pressed(Action::MoveRight) {
+        gs.current.inputs += 1;
     }
 
     // only allow on rotation per frame
-    if is_key_pressed(KeyCode::Up) {
+    if bindings.pressed(Action::RotateCw) {
         srs::rotate(true, &mut gs.current, &gs.placed_blocks, &mut gs.ghost);
         gs.last_input = FrameInput::Rotate;
-    } else if is_key_pressed(KeyCode::Z) {
+        gs.current.inputs += 1;
+    } else if bindings.pressed(Action::RotateCcw) {
         srs::rotate(false, &mut gs.current, &gs.placed_blocks, &mut gs.ghost);
         gs.last_input = FrameInput::Rotate;
+        gs.current.inputs += 1;
+    } else if bindings.pressed(Action::Rotate180) {
+        // two quarter turns, each with its own kicks
+        srs::rotate(true, &mut gs.current, &gs.placed_blocks, &mut gs.ghost);
+        srs::rotate(true, &mut gs.current, &gs.placed_blocks, &mut gs.ghost);
+        gs.last_input = FrameInput::Rotate;
+        gs.current.inputs += 1;
     }
 
-    if is_key_down(KeyCode::Down) {
-        gs.gravity.max = SOFT_DROP_GRAVITY;
+    if bindings.down(Action::SoftDrop) {
+        let gravity = modes::gravity_curve(gs).frames_per_row(gs.score.level);
+        gs.gravity.max = gs.options.handling.soft_drop.frames_per_row(gravity);
+        gs.gravity.soft_drop = true;
     }
-    if is_key_released(KeyCode::Down) {
-        gs.gravity.max = get_level_gravity_max(gs.score.level);
+    if bindings.released(Action::SoftDrop) {
+        gs.gravity.max = modes::frames_per_row(gs);
+        gs.gravity.soft_drop = false;
     }
-    if is_key_pressed(KeyCode::Space) && !gs.ghost.dirty {
+    if bindings.pressed(Action::HardDrop) && !gs.ghost.dirty {
         gs.current.sonic_lock = true;
+        gs.current.hard_dropped = (gs.current.pos.y - gs.ghost.pos.y) as usize;
         gs.current.pos = gs.ghost.pos
     }
-    if is_key_pressed(KeyCode::R) {
-        let textures = gs.textures;
-        let font = gs.font;
-        *gs = get_game_state(GameMode::Play);
-        gs.textures = textures;
-        gs.font = font;
+    if bindings.pressed(Action::Restart) {
+        reset_game_state(gs, GameMode::Play);
     }
-    if is_key_pressed(KeyCode::C) {
-        if gs.current.held {
+    if bindings.pressed(Action::Hold) {
+        if gs.current.held || !modes::can_hold(gs) {
             return;
         }
 
@@ -161,6 +436,7 @@ pub fn play_input(gs: &mut GameState) {
                 let mut temp = gs.current;
                 gs.current = hold;
                 gs.current.held = true;
+                gs.spawns += 1;
                 reset_transform(&mut temp);
                 gs.hold = Some(temp);
             }
@@ -174,10 +450,10 @@ pub fn play_input(gs: &mut GameState) {
         }
         gs.ghost.dirty = true;
     }
-    if is_key_pressed(KeyCode::P) {
+    if bindings.pressed(Action::Pause) {
         gs.game_mode = GameMode::Pause;
     }
-    if is_key_pressed(KeyCode::G) {
+    if bindings.pressed(Action::Debug) {
         gs.debug = !gs.debug;
     }
 }
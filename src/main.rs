@@ -6,10 +6,22 @@ mod draw;
 mod spawner;
 use draw::*;
 mod input;
+mod menu;
+mod modes;
 use input::*;
 mod collision;
 mod gravity_system;
 use gravity_system::*;
+mod attack;
+mod config;
+mod controls;
+mod custom;
+mod demo;
+mod finesse;
+mod gamepad;
+mod puzzle;
+mod scoring;
+use scoring::{callout_names, LockEvent};
 use spawner::drain_next;
 mod srs;
 
@@ -34,24 +46,46 @@ fn update_ghost(gs: &mut GameState) {
     gs.ghost.pos = pos;
 }
 
-fn calculate_score(gs: &mut GameState, completed_lines: &Vec<usize>, t_spin: bool) -> usize {
-    let n = completed_lines.len();
-    gs.score.lines += n;
-    gs.score.level = gs.score.lines / 10;
-    let mut score = match n {
-        1 => 40 * (n + 1),
-        2 => 100 * (n + 1),
-        3 => 300 * (n + 1),
-        // T-Spin 400 * (n + 1),
-        4 => 1200 * (n + 1),
-        _ => 0,
+fn calculate_score(gs: &mut GameState, completed_lines: &Vec<usize>, spin: SpinType) -> usize {
+    // a big piece clears rows in pairs
+    let n = completed_lines.len() / gs.current.scale;
+    let mut event = LockEvent {
+        lines: n,
+        spin,
+        combo: if n > 0 { gs.score.combo } else { 0 },
+        b2b: false,
+        perfect_clear: n > 0 && collision::is_perfect_clear(&gs.placed_blocks, completed_lines),
+        soft_drop: gs.current.soft_dropped,
+        hard_drop: gs.current.hard_dropped,
+        level: gs.score.level,
     };
-
-    if t_spin {
-        score += 400 * (n + 1);
+    let difficult = event.is_difficult();
+    event.b2b = difficult && gs.score.b2b;
+
+    if n > 0 {
+        gs.score.combo += 1;
+        gs.score.b2b = difficult;
+    } else {
+        gs.score.combo = 0;
+    }
+    add_lines(gs, n);
+    gs.score.attack += attack::lines_sent(&gs.options.attack_table, &event);
+    for text in callout_names(&event) {
+        gs.callouts.push(Callout { text, creation: 0 });
     }
 
-    score
+    gs.scoring.score(&event)
+}
+
+fn add_lines(gs: &mut GameState, n: usize) {
+    gs.score.lines += n;
+    if gs.options.game_type.levels_up() {
+        let (level_rule, start_level) = modes::level_rule(gs);
+        gs.score.level = level_for_lines(level_rule, start_level, gs.score.lines);
+    }
+    if !gs.gravity.soft_drop {
+        gs.gravity.max = modes::frames_per_row(gs);
+    }
 }
 
 fn has_block(placed: &Vec<Option<Block>>, x: f32, y: f32) -> bool {
@@ -65,10 +99,11 @@ fn has_block(placed: &Vec<Option<Block>>, x: f32, y: f32) -> bool {
 
 fn commit_tetromino(gs: &mut GameState) {
     if gs.current.pos.cmpeq(gs.current.spawn_pos).all() {
-        gs.score.topout = true;
+        modes::on_topout(gs);
         return;
     }
 
+    gs.score.chain = 0;
     // first we place all the blocks on the board
     let points = gs.current.relative_points(&gs.current.pos);
     for p in points.iter() {
@@ -76,10 +111,13 @@ fn commit_tetromino(gs: &mut GameState) {
             // color: gs.current.color,
             color: LIGHT,
             kind: gs.current.kind,
+            garbage: false,
+            locked: gs.score.frames,
+            piece: modes::pieces(gs) + 1,
         });
     }
 
-    let t_spin_occured = match (&gs.current.kind, &gs.last_input) {
+    let spin = match (&gs.current.kind, &gs.last_input) {
         (TetrominoType::T, FrameInput::Rotate) => {
             // Each t-block rotation has a different range of bounds that we need to check
             // visualise the "keyhole" in blocks that a t-block can fill
@@ -115,62 +153,180 @@ fn commit_tetromino(gs: &mut GameState) {
                 })
             });
 
-            t_spin_occured
+            if t_spin_occured {
+                SpinType::Full
+            } else {
+                // 3-corner rule: a rotated t-block with three corners of its center covered
+                let scale = gs.current.scale as f32;
+                let center = vec2(
+                    gs.current.pos.x + 1.0,
+                    WELL_HEIGHT as f32 / scale - (gs.current.pos.y + 1.0),
+                );
+                let corners = [vec2(-1., -1.), vec2(1., -1.), vec2(-1., 1.), vec2(1., 1.)];
+                let covered = corners
+                    .iter()
+                    .filter(|dp| {
+                        let x = (center.x + dp.x) * scale;
+                        let y = (center.y + dp.y) * scale;
+                        x < 0.0
+                            || x >= WELL_WIDTH as f32
+                            || y >= WELL_HEIGHT as f32
+                            || (y >= 0.0 && has_block(&gs.placed_blocks, x, y))
+                    })
+                    .count();
+
+                if covered >= 3 {
+                    SpinType::Mini
+                } else {
+                    SpinType::None
+                }
+            }
         }
-        _ => false,
+        _ => SpinType::None,
     };
 
+    // tucks and spins need soft drops, finesse only judges pieces placed from above
+    if gs.current.soft_dropped == 0 {
+        if let Some(spawned) = gs.tetrominos.iter().find(|t| t.kind == gs.current.kind) {
+            let minimal = finesse::minimal_inputs(spawned, &gs.current);
+            gs.score.finesse_faults += gs.current.inputs.saturating_sub(minimal);
+        }
+    }
+
     let stat = gs.statistics.get_mut(&gs.current.kind);
     match stat {
         Some(stat_val) => *stat_val += 1,
         _ => {}
     }
 
-    gs.current = drain_next(gs);
     let completed_lines = collision::completed_lines(&gs.placed_blocks);
+    let score = calculate_score(gs, &completed_lines, spin);
+    gs.score.val += score;
+    if gs.next.is_empty() {
+        // a fixed queue ran out, only a held piece is left to play
+        if let Some(hold) = gs.hold.take() {
+            gs.current = hold;
+            gs.ghost.dirty = true;
+            gs.gravity.meter = 0.0;
+            gs.spawns += 1;
+        }
+    } else {
+        gs.current = drain_next(gs);
+    }
+    modes::on_lock(gs, &completed_lines, spin);
     if completed_lines.len() > 0 {
-        let score = calculate_score(gs, &completed_lines, t_spin_occured);
-        gs.score.val += score;
         gs.last_score = ScorePopup {
             val: score,
             creation: 0,
         };
 
         gs.line_clear = Some(LineClear {
-            y_pos: *completed_lines.iter().min().unwrap(),
             lines: completed_lines.clone(),
             counter: 0,
         });
     }
+    modes::check_goal(gs);
 }
 
-fn remove_lines(placed_blocks: &mut Vec<Option<Block>>, completed_lines: &Vec<usize>) {
+fn remove_lines(
+    placed_blocks: &mut Vec<Option<Block>>,
+    completed_lines: &Vec<usize>,
+    line_gravity: LineGravity,
+) {
     spawner::despawn_blocks(placed_blocks, &completed_lines);
-    apply_gravity(placed_blocks, &completed_lines);
+    match line_gravity {
+        LineGravity::Naive => apply_gravity(placed_blocks, &completed_lines),
+        LineGravity::Sticky => apply_group_gravity(placed_blocks, false),
+        LineGravity::Cascade => apply_group_gravity(placed_blocks, true),
+    }
+}
+
+// lines filled by sticky or cascade gravity clear on their own and extend the chain
+fn chain_clear(gs: &mut GameState) {
+    let completed_lines = collision::completed_lines(&gs.placed_blocks);
+    if completed_lines.is_empty() {
+        return;
+    }
+
+    gs.score.chain += 1;
+    let n = completed_lines.len() / gs.current.scale;
+    let event = LockEvent {
+        lines: n,
+        spin: SpinType::None,
+        combo: 0,
+        b2b: false,
+        perfect_clear: collision::is_perfect_clear(&gs.placed_blocks, &completed_lines),
+        soft_drop: 0,
+        hard_drop: 0,
+        level: gs.score.level,
+    };
+    add_lines(gs, n);
+    let score = gs.scoring.score(&event);
+    gs.score.val += score;
+    gs.last_score = ScorePopup {
+        val: score,
+        creation: 0,
+    };
+    gs.callouts.push(Callout {
+        text: format!("{} CHAIN", gs.score.chain + 1),
+        creation: 0,
+    });
+    gs.line_clear = Some(LineClear {
+        lines: completed_lines,
+        counter: 0,
+    });
+    modes::check_goal(gs);
 }
 
 fn update(gs: &mut GameState) {
     match gs.game_mode {
         GameMode::Play => play_update(gs),
+        GameMode::Title => {
+            gs.idle += 1;
+            if gs.idle >= demo::DEMO_IDLE_FRAMES {
+                demo::start(gs);
+            }
+        }
+        GameMode::Demo => {
+            demo::drive(gs);
+            play_update(gs);
+            // the demo never reaches the ending screen
+            if gs.score.topout
+                || gs.game_mode != GameMode::Demo
+                || gs.score.frames >= demo::DEMO_FRAMES
+            {
+                demo::stop(gs);
+            }
+        }
         _ => {}
     }
 }
 
 fn play_update(gs: &mut GameState) {
+    gs.score.frames += 1;
+    modes::update(gs);
     gs.gravity.meter += 1.0;
-    if gs.current.entry_timer < ENTRY_DELAY {
+    if gs.current.entry_timer < gs.delays.entry {
         gs.current.entry_timer += 1;
     }
     if gs.last_score.val > 0 && gs.last_score.creation < SCORE_TIMEOUT {
         gs.last_score.creation += 1;
     }
+    for callout in gs.callouts.iter_mut() {
+        callout.creation += 1;
+    }
+    gs.callouts.retain(|c| c.creation < CALLOUT_TIMEOUT);
+    let line_gravity = modes::line_gravity(gs);
     match &mut gs.line_clear {
         Some(line_clear) => {
             line_clear.counter += 1;
-            if line_clear.counter >= LINE_CLEAR_DELAY {
-                remove_lines(&mut gs.placed_blocks, &line_clear.lines);
+            if line_clear.counter >= gs.delays.line_clear {
+                remove_lines(&mut gs.placed_blocks, &line_clear.lines, line_gravity);
                 gs.line_clear = None;
                 gs.ghost.dirty = true;
+                if line_gravity != LineGravity::Naive {
+                    chain_clear(gs);
+                }
             }
         }
         None => {}
@@ -192,18 +348,29 @@ fn play_update(gs: &mut GameState) {
     }
 
     if on_surface
-        && (gs.current.sonic_lock || (gs.current.locking && gs.current.lock_counter >= LOCK_DELAY))
+        && (gs.current.sonic_lock
+            || (gs.current.locking && gs.current.lock_counter >= gs.delays.lock))
     {
         debug!("commiting");
         debug!("on_surface={}", on_surface);
         debug!("gs.current.locking={}", gs.current.locking);
         debug!("gs.current.lock_counter={}", gs.current.lock_counter);
-        debug!("LOCK_DELAY={}", LOCK_DELAY);
+        debug!("gs.delays.lock={}", gs.delays.lock);
         commit_tetromino(gs);
     }
 
-    if !on_surface && gs.gravity.meter >= gs.gravity.max && gs.current.entry_timer >= ENTRY_DELAY {
-        move_downwards(gs);
+    if !on_surface
+        && gs.gravity.meter >= gs.gravity.max
+        && gs.current.entry_timer >= gs.delays.entry
+    {
+        // gravity faster than a row per frame moves several rows at once
+        let rows = f32::max(1.0 / gs.gravity.max, 1.0) as usize;
+        for _ in 0..rows {
+            if should_commit_tetromino(&gs.current, &gs.current.pos, &gs.placed_blocks) {
+                break;
+            }
+            move_downwards(gs);
+        }
     }
 }
 
@@ -222,17 +389,41 @@ fn window_conf() -> window::Conf {
 async fn main() {
     rand::srand(macroquad::miniquad::date::now() as u64);
 
-    let mut gs = get_game_state(GameMode::Title);
+    let mut options = Options::default();
+    if let Ok(settings) = load_string(config::SETTINGS_PATH).await {
+        options.apply_config(&config::Config::parse(&settings));
+    }
+    let settings = options.clone();
+    if let Some(saved) = config::load(config::OPTIONS_PATH) {
+        options.apply_config(&saved);
+    }
+    let mut gs = get_game_state(GameMode::Title, options);
+    gs.settings = settings;
+    gamepad::init();
     let blocks_texture: Texture2D = load_texture("assets/blocks.png").await.unwrap();
     let font = load_ttf_font("assets/visitor.ttf").await.unwrap();
     gs.textures = blocks_texture;
     gs.font = font;
+    if let Ok(puzzles) = load_string(puzzle::PUZZLES_PATH).await {
+        gs.puzzles = puzzle::parse(&puzzles);
+        modes::load_cleared_puzzles(&mut gs.puzzles);
+    }
+    if let Ok(custom_modes) = load_string(custom::CUSTOM_MODES_PATH).await {
+        gs.custom_modes = custom::parse(&custom_modes);
+    }
+    // the remembered custom mode may have left the file
+    if let GameType::Custom(idx) = gs.options.game_type {
+        if idx >= gs.custom_modes.len() {
+            gs.options.game_type = GameType::Marathon;
+        }
+    }
 
     loop {
         gs.scl = screen_width() / GAME_WIDTH;
 
+        gamepad::update(gs.options.bindings.deadzone);
+        input(&mut gs);
         if !gs.score.topout {
-            input(&mut gs);
             update(&mut gs);
         }
 
@@ -241,3 +432,21 @@ async fn main() {
         next_frame().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ultra_ignores_remembered_start_level() {
+        let options = Options {
+            game_type: GameType::Ultra,
+            start_level: 9,
+            level_rule: LevelRule::Nes,
+            ..Default::default()
+        };
+        let mut gs = get_game_state(GameMode::Play, options);
+        add_lines(&mut gs, 1);
+        assert_eq!(gs.score.level, 0);
+    }
+}
@@ -0,0 +1,240 @@
+/*
+ Game type and option menus
+*/
+use super::{
+    attack::AttackTable,
+    components::{GameState, GameType, LevelRule, Options, MAX_ARR, MAX_DAS, MAX_DAS_CUT},
+    controls::DEADZONE_STEP,
+    modes::{B_TYPE_HEIGHTS, DIG_ROWS, MARATHON_GOALS, SPRINT_LINES, ULTRA_MINUTES},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MenuItem {
+    MarathonGoal,
+    Height,
+    SprintLines,
+    UltraTime,
+    DigRows,
+    DigStyle,
+    ZenGravity,
+    Stack,
+    LineGravity,
+    LevelRule,
+    Gravity,
+    Scoring,
+    Attack,
+    // one row per loaded puzzle
+    Mission(usize),
+    // handling, on the controls screen
+    Das,
+    Arr,
+    SoftDrop,
+    DasCharge,
+    DasCut,
+    Deadzone,
+}
+
+// rows shown below the actions on the controls screen
+pub fn handling_items() -> Vec<MenuItem> {
+    vec![
+        MenuItem::Das,
+        MenuItem::Arr,
+        MenuItem::SoftDrop,
+        MenuItem::DasCharge,
+        MenuItem::DasCut,
+        MenuItem::Deadzone,
+    ]
+}
+
+// option rows shown below the level grid
+pub fn option_items(gs: &GameState) -> Vec<MenuItem> {
+    match gs.options.game_type {
+        GameType::Marathon => vec![
+            MenuItem::MarathonGoal,
+            MenuItem::LevelRule,
+            MenuItem::Gravity,
+            MenuItem::Scoring,
+            MenuItem::Attack,
+            MenuItem::Stack,
+            MenuItem::LineGravity,
+        ],
+        GameType::BType => vec![
+            MenuItem::Height,
+            MenuItem::Gravity,
+            MenuItem::Scoring,
+            MenuItem::Attack,
+            MenuItem::Stack,
+        ],
+        GameType::Sprint => vec![MenuItem::SprintLines, MenuItem::Gravity, MenuItem::Stack],
+        GameType::Ultra => vec![
+            MenuItem::UltraTime,
+            MenuItem::Gravity,
+            MenuItem::Scoring,
+            MenuItem::Stack,
+        ],
+        GameType::Dig => vec![MenuItem::DigRows, MenuItem::DigStyle, MenuItem::Gravity],
+        GameType::Survival => vec![MenuItem::Gravity],
+        // rules are fixed to the arcade ones or those in the mode file
+        GameType::Master | GameType::Custom(_) => Vec::new(),
+        GameType::Zen => vec![
+            MenuItem::ZenGravity,
+            MenuItem::Gravity,
+            MenuItem::LineGravity,
+        ],
+        GameType::Big => vec![MenuItem::LevelRule, MenuItem::Gravity, MenuItem::Scoring],
+        GameType::Puzzle => (0..gs.puzzles.len()).map(MenuItem::Mission).collect(),
+    }
+}
+
+pub fn item_text(item: MenuItem, gs: &GameState) -> String {
+    let options = &gs.options;
+    match item {
+        MenuItem::MarathonGoal => {
+            format!("< GOAL {} >", MARATHON_GOALS[options.marathon_goal].name())
+        }
+        MenuItem::Height => format!("< HEIGHT {} >", options.height),
+        MenuItem::SprintLines => format!("< {} LINES >", SPRINT_LINES[options.sprint_goal]),
+        MenuItem::UltraTime => format!("< {} MINUTES >", ULTRA_MINUTES[options.ultra_time]),
+        MenuItem::DigRows => format!("< {} ROWS >", DIG_ROWS[options.dig_rows]),
+        MenuItem::DigStyle => {
+            format!("< {} >", if options.dig_messy { "MESSY" } else { "CLEAN" })
+        }
+        MenuItem::ZenGravity => {
+            format!(
+                "< GRAVITY {} >",
+                if options.zen_gravity { "ON" } else { "OFF" }
+            )
+        }
+        MenuItem::Stack => format!("< {} STACK >", options.stack.name()),
+        MenuItem::LineGravity => format!("< {} CLEARS >", options.line_gravity.name()),
+        MenuItem::LevelRule => format!("< {} LEVEL UP >", options.level_rule.name()),
+        MenuItem::Gravity => format!("< {} GRAVITY >", options.gravity_curve.name()),
+        MenuItem::Scoring => format!("< {} SCORING >", options.scoring.system().name()),
+        MenuItem::Attack => format!("< {} ATTACK >", options.attack_table.name),
+        MenuItem::Mission(idx) => {
+            let puzzle = &gs.puzzles[idx];
+            format!("{} {}", if puzzle.cleared { "*" } else { " " }, puzzle.name)
+        }
+        MenuItem::Das => format!("< DAS {} FRAMES >", options.handling.das),
+        MenuItem::Arr if options.handling.arr == 0 => "< ARR INSTANT >".to_string(),
+        MenuItem::Arr => format!("< ARR {} FRAMES >", options.handling.arr),
+        MenuItem::SoftDrop => format!("< SOFT DROP {} >", options.handling.soft_drop.name()),
+        MenuItem::DasCharge => format!(
+            "< DAS CHARGE {} >",
+            if options.handling.das_charge {
+                "ON"
+            } else {
+                "OFF"
+            }
+        ),
+        MenuItem::DasCut if options.handling.das_cut == 0 => "< DAS CUT OFF >".to_string(),
+        MenuItem::DasCut => format!("< DAS CUT {} FRAMES >", options.handling.das_cut),
+        MenuItem::Deadzone => format!("< DEADZONE {:.2} >", options.bindings.deadzone),
+    }
+}
+
+pub fn adjust_item(item: MenuItem, options: &mut Options, forward: bool) {
+    match item {
+        MenuItem::MarathonGoal => {
+            let len = MARATHON_GOALS.len();
+            options.marathon_goal = if forward {
+                (options.marathon_goal + 1) % len
+            } else {
+                (options.marathon_goal + len - 1) % len
+            };
+        }
+        MenuItem::Height => {
+            let len = B_TYPE_HEIGHTS.len();
+            options.height = if forward {
+                (options.height + 1) % len
+            } else {
+                (options.height + len - 1) % len
+            };
+        }
+        MenuItem::SprintLines => {
+            let len = SPRINT_LINES.len();
+            options.sprint_goal = if forward {
+                (options.sprint_goal + 1) % len
+            } else {
+                (options.sprint_goal + len - 1) % len
+            };
+        }
+        MenuItem::UltraTime => {
+            let len = ULTRA_MINUTES.len();
+            options.ultra_time = if forward {
+                (options.ultra_time + 1) % len
+            } else {
+                (options.ultra_time + len - 1) % len
+            };
+        }
+        MenuItem::DigRows => {
+            let len = DIG_ROWS.len();
+            options.dig_rows = if forward {
+                (options.dig_rows + 1) % len
+            } else {
+                (options.dig_rows + len - 1) % len
+            };
+        }
+        MenuItem::DigStyle => options.dig_messy = !options.dig_messy,
+        MenuItem::ZenGravity => options.zen_gravity = !options.zen_gravity,
+        MenuItem::Stack => options.stack = options.stack.next(forward),
+        MenuItem::LineGravity => options.line_gravity = options.line_gravity.next(forward),
+        MenuItem::LevelRule => {
+            options.level_rule = match options.level_rule {
+                LevelRule::Nes => LevelRule::Guideline,
+                LevelRule::Guideline => LevelRule::Nes,
+            };
+        }
+        MenuItem::Gravity => options.gravity_curve = options.next_gravity_curve(forward),
+        MenuItem::Scoring => {
+            options.scoring = if forward {
+                options.scoring.next()
+            } else {
+                options.scoring.prev()
+            };
+        }
+        MenuItem::Attack => {
+            let presets = AttackTable::presets();
+            let len = presets.len();
+            let current = presets
+                .iter()
+                .position(|t| t.name == options.attack_table.name)
+                .unwrap_or(0);
+            let idx = if forward {
+                (current + 1) % len
+            } else {
+                (current + len - 1) % len
+            };
+            options.attack_table = presets[idx].clone();
+        }
+        MenuItem::Mission(_) => {}
+        MenuItem::Das => {
+            options.handling.das = if forward {
+                usize::min(options.handling.das + 1, MAX_DAS)
+            } else {
+                options.handling.das.saturating_sub(1)
+            };
+        }
+        MenuItem::Arr => {
+            options.handling.arr = if forward {
+                usize::min(options.handling.arr + 1, MAX_ARR)
+            } else {
+                options.handling.arr.saturating_sub(1)
+            };
+        }
+        MenuItem::SoftDrop => options.handling.soft_drop = options.handling.soft_drop.next(forward),
+        MenuItem::DasCharge => options.handling.das_charge = !options.handling.das_charge,
+        MenuItem::DasCut => {
+            options.handling.das_cut = if forward {
+                usize::min(options.handling.das_cut + 1, MAX_DAS_CUT)
+            } else {
+                options.handling.das_cut.saturating_sub(1)
+            };
+        }
+        MenuItem::Deadzone => options.bindings.adjust_deadzone(if forward {
+            DEADZONE_STEP
+        } else {
+            -DEADZONE_STEP
+        }),
+    }
+}
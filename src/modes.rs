@@ -0,0 +1,882 @@
+/*
+ Game type rules
+ Starting boards, goals and what the HUD counts for each game type.
+*/
+use super::{
+    collision,
+    components::{
+        Block, Delays, GameMode, GameState, GameType, GravityCurve, LevelRule, LineGravity,
+        Randomizer, SpinType, StackVisibility, Tetromino, TetrominoType, WELL_HEIGHT, WELL_WIDTH,
+    },
+    config,
+    custom::{Board, CustomMode, End, Goal},
+    menu::{option_items, MenuItem},
+    puzzle::{Objective, Puzzle},
+    scoring::ScoringKind,
+    spawner, xy_idx,
+};
+
+pub const B_TYPE_LINES: usize = 25;
+pub const B_TYPE_HEIGHTS: [usize; 6] = [0, 3, 5, 8, 10, 12];
+pub const SPRINT_LINES: [usize; 3] = [20, 40, 100];
+pub const DIG_ROWS: [usize; 3] = [5, 10, 18];
+// frames between rising garbage rows, each rise is 5% sooner
+pub const SURVIVAL_START_INTERVAL: usize = 600;
+pub const SURVIVAL_MIN_INTERVAL: usize = 90;
+pub const ULTRA_MINUTES: [usize; 2] = [2, 3];
+pub const HIGH_SCORE_ENTRIES: usize = 10;
+// rows taken off the bottom when a zen stack reaches the top
+pub const ZEN_CLEARED_ROWS: usize = 10;
+// big mode plays on a 5x11 grid inside the well
+pub const BIG_SCALE: usize = 2;
+// a fading block starts to disappear after the delay and is gone after the fade
+pub const FADE_DELAY: usize = 180;
+pub const FADE_FRAMES: usize = 60;
+pub const MASTER_LEVEL: usize = 999;
+// points needed for each grade, from the 1998 arcade game
+const MASTER_GRADES: [(usize, &str); 18] = [
+    (0, "9"),
+    (400, "8"),
+    (800, "7"),
+    (1400, "6"),
+    (2000, "5"),
+    (3500, "4"),
+    (5500, "3"),
+    (8000, "2"),
+    (12000, "1"),
+    (16000, "S1"),
+    (22000, "S2"),
+    (30000, "S3"),
+    (40000, "S4"),
+    (52000, "S5"),
+    (66000, "S6"),
+    (82000, "S7"),
+    (100000, "S8"),
+    (120000, "S9"),
+];
+// grand master needs the points and levels 300, 500 and 999 within 4:15, 7:30 and 13:30
+const GRAND_MASTER_POINTS: usize = 126000;
+const GRAND_MASTER_SPLITS: [(usize, usize); 3] = [(2, 15300), (4, 27000), (9, 48600)];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MarathonGoal {
+    Lines(usize),
+    Level(usize),
+    Endless,
+}
+
+pub const MARATHON_GOALS: [MarathonGoal; 4] = [
+    MarathonGoal::Lines(150),
+    MarathonGoal::Lines(200),
+    MarathonGoal::Level(15),
+    MarathonGoal::Endless,
+];
+
+impl MarathonGoal {
+    pub fn name(self) -> String {
+        match self {
+            MarathonGoal::Lines(lines) => format!("{} LINES", lines),
+            MarathonGoal::Level(level) => format!("LEVEL {}", level),
+            MarathonGoal::Endless => "ENDLESS".to_string(),
+        }
+    }
+}
+
+pub fn start(gs: &mut GameState) {
+    match gs.options.game_type {
+        GameType::BType => {
+            spawner::fill_garbage(&mut gs.placed_blocks, B_TYPE_HEIGHTS[gs.options.height]);
+        }
+        GameType::Dig => {
+            let rows = DIG_ROWS[gs.options.dig_rows];
+            spawner::fill_cheese(&mut gs.placed_blocks, rows, gs.options.dig_messy);
+        }
+        GameType::Master => {
+            gs.scoring = ScoringKind::Tgm.system();
+            gs.delays = master_delays(0);
+            gs.gravity.max = GravityCurve::Tgm.frames_per_row(0);
+        }
+        GameType::Zen => gs.gravity.max = frames_per_row(gs),
+        GameType::Big => {
+            for tetromino in gs.tetrominos.iter_mut() {
+                spawner::set_scale(tetromino, BIG_SCALE);
+            }
+            for tetromino in gs.next.iter_mut() {
+                spawner::set_scale(tetromino, BIG_SCALE);
+            }
+            spawner::set_scale(&mut gs.current, BIG_SCALE);
+            gs.ghost.dirty = true;
+        }
+        GameType::Puzzle => {
+            if let Some(puzzle) = gs.puzzles.get(gs.options.puzzle).cloned() {
+                gs.placed_blocks = puzzle.board();
+                let mut queue: Vec<Tetromino> = puzzle
+                    .queue
+                    .iter()
+                    .filter_map(|kind| template(gs, *kind))
+                    .collect();
+                gs.current = queue.remove(0);
+                gs.next = queue;
+                gs.hold = puzzle.held.and_then(|kind| template(gs, kind));
+                gs.ghost.dirty = true;
+            }
+        }
+        GameType::Custom(idx) => {
+            if let Some(mode) = gs.custom_modes.get(idx).cloned() {
+                match mode.board {
+                    Board::Empty => {}
+                    Board::Garbage(rows) => spawner::fill_garbage(&mut gs.placed_blocks, rows),
+                    Board::Cheese(rows) => spawner::fill_cheese(&mut gs.placed_blocks, rows, false),
+                    Board::Messy(rows) => spawner::fill_cheese(&mut gs.placed_blocks, rows, true),
+                }
+                gs.scoring = mode.scoring.system();
+                gs.score.level = mode.level;
+                gs.gravity.max = mode.gravity_curve.frames_per_row(mode.level);
+                gs.randomizer = mode.randomizer;
+                // the first pieces were dealt before the randomizer was known
+                if mode.randomizer == Randomizer::Bag {
+                    gs.current = spawner::next_tetromino(gs);
+                    let count = gs.next.len();
+                    gs.next = (0..count).map(|_| spawner::next_tetromino(gs)).collect();
+                    gs.ghost.dirty = true;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn custom_mode(gs: &GameState) -> Option<&CustomMode> {
+    match gs.options.game_type {
+        GameType::Custom(idx) => gs.custom_modes.get(idx),
+        _ => None,
+    }
+}
+
+// built in game types followed by the custom modes
+pub fn game_types(gs: &GameState) -> Vec<GameType> {
+    let mut game_types = GameType::all();
+    game_types.extend((0..gs.custom_modes.len()).map(GameType::Custom));
+
+    game_types
+}
+
+pub fn game_type_name(gs: &GameState, game_type: GameType) -> String {
+    match game_type {
+        GameType::Custom(idx) => match gs.custom_modes.get(idx) {
+            Some(mode) => mode.name.clone(),
+            None => game_type.name().to_string(),
+        },
+        _ => game_type.name().to_string(),
+    }
+}
+
+pub fn game_type_description(gs: &GameState, game_type: GameType) -> String {
+    match game_type {
+        GameType::Custom(idx) => match gs.custom_modes.get(idx) {
+            Some(mode) => mode.description.clone(),
+            None => game_type.description().to_string(),
+        },
+        _ => game_type.description().to_string(),
+    }
+}
+
+// called every frame of play
+pub fn update(gs: &mut GameState) {
+    match gs.options.game_type {
+        GameType::Ultra => {
+            if gs.score.frames >= ultra_frames(gs) {
+                finish_ultra(gs);
+            }
+        }
+        GameType::Survival => {
+            // rows are not pushed while cleared lines are still on the board
+            gs.garbage_timer += 1;
+            if gs.garbage_timer >= gs.garbage_interval && gs.line_clear.is_none() {
+                gs.garbage_timer = 0;
+                gs.garbage_interval =
+                    usize::max(gs.garbage_interval * 95 / 100, SURVIVAL_MIN_INTERVAL);
+                rise_garbage(gs);
+            }
+        }
+        GameType::Custom(_) => {
+            let (goal, end) = match custom_mode(gs) {
+                Some(mode) => (mode.goal, mode.end),
+                None => return,
+            };
+            if matches!(goal, Goal::Time(frames) if gs.score.frames >= frames) {
+                gs.score.solved = true;
+                gs.game_mode = GameMode::Ending;
+            } else if matches!(end, End::Time(frames) if gs.score.frames >= frames) {
+                gs.game_mode = GameMode::Ending;
+            }
+        }
+        GameType::Marathon
+        | GameType::BType
+        | GameType::Sprint
+        | GameType::Dig
+        | GameType::Master
+        | GameType::Zen
+        | GameType::Puzzle
+        | GameType::Big => {}
+    }
+}
+
+fn template(gs: &GameState, kind: TetrominoType) -> Option<Tetromino> {
+    gs.tetrominos.iter().find(|t| t.kind == kind).copied()
+}
+
+pub fn can_hold(gs: &GameState) -> bool {
+    match gs.options.game_type {
+        GameType::Puzzle => {
+            let allowed = match gs.puzzles.get(gs.options.puzzle) {
+                Some(puzzle) => puzzle.hold_allowed,
+                None => true,
+            };
+            // holding into an empty hold needs a next piece
+            allowed && (gs.hold.is_some() || !gs.next.is_empty())
+        }
+        GameType::Custom(_) => custom_mode(gs).is_none_or(|mode| mode.hold_allowed),
+        _ => true,
+    }
+}
+
+// master mode always runs on the arcade curve
+pub fn gravity_curve(gs: &GameState) -> GravityCurve {
+    match gs.options.game_type {
+        GameType::Master => GravityCurve::Tgm,
+        GameType::Custom(_) => match custom_mode(gs) {
+            Some(mode) => mode.gravity_curve.clone(),
+            None => gs.options.gravity_curve.clone(),
+        },
+        _ => gs.options.gravity_curve.clone(),
+    }
+}
+
+// only game types that offer the choice hide the stack
+pub fn stack_visibility(gs: &GameState) -> StackVisibility {
+    if option_items(gs).contains(&MenuItem::Stack) {
+        gs.options.stack
+    } else {
+        StackVisibility::Visible
+    }
+}
+
+// only game types that offer the choice use it, the rest clear naively
+pub fn line_gravity(gs: &GameState) -> LineGravity {
+    if option_items(gs).contains(&MenuItem::LineGravity) {
+        gs.options.line_gravity
+    } else {
+        LineGravity::Naive
+    }
+}
+
+// rule and starting level that lines level up from
+pub fn level_rule(gs: &GameState) -> (LevelRule, usize) {
+    match custom_mode(gs) {
+        Some(mode) => (mode.level_rule, mode.level),
+        // no start level or rule on offer, so ignore the remembered ones
+        None if !gs.options.game_type.has_level() => (LevelRule::Guideline, 0),
+        None => (gs.options.level_rule, gs.options.start_level),
+    }
+}
+
+// current gravity, a zen game without gravity never reaches it
+pub fn frames_per_row(gs: &GameState) -> f32 {
+    if gs.options.game_type == GameType::Zen && !gs.options.zen_gravity {
+        return f32::INFINITY;
+    }
+
+    gravity_curve(gs).frames_per_row(gs.score.level)
+}
+
+// arcade timings per hundred levels
+fn master_delays(level: usize) -> Delays {
+    let (entry, lock, line_clear) = match level {
+        0..=499 => (25, 30, 40),
+        500..=599 => (25, 30, 25),
+        600..=699 => (25, 30, 16),
+        700..=799 => (16, 30, 12),
+        800..=899 => (12, 30, 6),
+        _ => (12, 17, 6),
+    };
+
+    Delays {
+        entry,
+        lock,
+        line_clear,
+    }
+}
+
+// called after every lock, before the goal is checked
+pub fn on_lock(gs: &mut GameState, completed: &[usize], spin: SpinType) {
+    let lines = completed.len();
+    match gs.options.game_type {
+        GameType::Master => {
+            // a piece alone never passes the last level of a section
+            let before = gs.score.level;
+            let stop = if before >= 900 {
+                MASTER_LEVEL - 1
+            } else {
+                before / 100 * 100 + 99
+            };
+            let level = usize::min(usize::min(before + 1, stop) + lines, MASTER_LEVEL);
+            for _ in before / 100..level / 100 {
+                gs.section_splits.push(gs.score.frames);
+            }
+            if level >= MASTER_LEVEL {
+                gs.section_splits.push(gs.score.frames);
+            }
+
+            gs.score.level = level;
+            gs.delays = master_delays(level);
+            if !gs.gravity.soft_drop {
+                gs.gravity.max = GravityCurve::Tgm.frames_per_row(level);
+            }
+        }
+        GameType::Marathon
+        | GameType::BType
+        | GameType::Sprint
+        | GameType::Ultra
+        | GameType::Dig
+        | GameType::Survival
+        | GameType::Zen
+        | GameType::Big
+        | GameType::Custom(_) => {}
+        GameType::Puzzle => {
+            let (objective, pieces_given) = match gs.puzzles.get(gs.options.puzzle) {
+                Some(puzzle) => (puzzle.objective, puzzle.pieces()),
+                None => return,
+            };
+            gs.score.solved = match objective {
+                Objective::Lines(goal) => gs.score.lines >= goal,
+                Objective::TSpinTriple => spin == SpinType::Full && lines == 3,
+                Objective::PerfectClear => {
+                    lines > 0 && collision::is_perfect_clear(&gs.placed_blocks, completed)
+                }
+                Objective::NoHoles => !collision::has_holes(&gs.placed_blocks, completed),
+            };
+            if gs.score.solved {
+                store_cleared_puzzle(gs);
+                gs.game_mode = GameMode::Ending;
+            } else if pieces(gs) >= pieces_given {
+                gs.game_mode = GameMode::Ending;
+            }
+        }
+    }
+}
+
+fn store_cleared_puzzle(gs: &mut GameState) {
+    let idx = gs.options.puzzle;
+    gs.puzzles[idx].cleared = true;
+    let cleared: Vec<String> = gs
+        .puzzles
+        .iter()
+        .filter(|p| p.cleared)
+        .map(|p| p.name.clone())
+        .collect();
+    let mut records = config::load(config::RECORDS_PATH).unwrap_or_default();
+    records.set("puzzles_cleared", cleared.join(", "));
+    config::save(config::RECORDS_PATH, &records.serialize());
+}
+
+// marks puzzles solved in an earlier session, by name
+pub fn load_cleared_puzzles(puzzles: &mut [Puzzle]) {
+    let records = config::load(config::RECORDS_PATH).unwrap_or_default();
+    let cleared: Vec<&str> = records
+        .get("puzzles_cleared")
+        .unwrap_or("")
+        .split(',')
+        .map(|name| name.trim())
+        .collect();
+    for puzzle in puzzles.iter_mut() {
+        puzzle.cleared = cleared.contains(&puzzle.name.as_str());
+    }
+}
+
+pub fn master_grade(gs: &GameState) -> &'static str {
+    let splits_made = GRAND_MASTER_SPLITS
+        .iter()
+        .all(|(section, frames)| matches!(gs.section_splits.get(*section), Some(split) if split <= frames));
+    if gs.score.val >= GRAND_MASTER_POINTS && splits_made {
+        return "GM";
+    }
+
+    MASTER_GRADES
+        .iter()
+        .rev()
+        .find(|(points, _)| gs.score.val >= *points)
+        .map(|(_, grade)| *grade)
+        .unwrap_or("9")
+}
+
+// time spent in each finished section
+fn section_times(gs: &GameState) -> Vec<usize> {
+    let mut last = 0;
+    gs.section_splits
+        .iter()
+        .map(|split| {
+            let time = split - last;
+            last = *split;
+            time
+        })
+        .collect()
+}
+
+fn rise_garbage(gs: &mut GameState) {
+    let overflow = spawner::push_garbage_row(&mut gs.placed_blocks, gs.score.frames);
+    gs.current.pos.y += 1.0;
+    // an unmoved piece still tops out when it locks
+    gs.current.spawn_pos.y += 1.0;
+    gs.ghost.dirty = true;
+
+    let overlap = gs
+        .current
+        .relative_points(&gs.current.pos)
+        .iter()
+        .any(|p| p.y >= 0.0 && gs.placed_blocks[xy_idx(p.x, p.y)].is_some());
+    if overflow || overlap {
+        on_topout(gs);
+    }
+}
+
+// drops the stack by the given rows, the bottom ones fall out of the well
+fn clear_bottom_rows(gs: &mut GameState, rows: usize) {
+    let cleared = rows * WELL_WIDTH;
+    let len = gs.placed_blocks.len();
+    gs.placed_blocks.copy_within(0..len - cleared, cleared);
+    for block in gs.placed_blocks[..cleared].iter_mut() {
+        *block = None;
+    }
+    gs.ghost.dirty = true;
+}
+
+// called when the stack reaches the top
+pub fn on_topout(gs: &mut GameState) {
+    if gs.options.game_type == GameType::Zen {
+        clear_bottom_rows(gs, ZEN_CLEARED_ROWS);
+        return;
+    }
+
+    gs.score.topout = true;
+    if gs.options.game_type == GameType::Ultra {
+        finish_ultra(gs);
+    }
+}
+
+// opacity of a placed block, the whole stack shows again once the game is over
+pub fn block_alpha(gs: &GameState, block: &Block) -> f32 {
+    if gs.score.topout || gs.game_mode == GameMode::Ending {
+        return 1.0;
+    }
+    match stack_visibility(gs) {
+        StackVisibility::Visible => 1.0,
+        StackVisibility::Fading => {
+            let age = gs.score.frames.saturating_sub(block.locked);
+            let faded = age.saturating_sub(FADE_DELAY) as f32 / FADE_FRAMES as f32;
+            1.0 - f32::min(faded, 1.0)
+        }
+        StackVisibility::Invisible => 0.0,
+    }
+}
+
+// timers run at 60 frames per second
+pub fn format_ticks(frames: usize) -> String {
+    let millis = frames * 1000 / 60;
+    format!(
+        "{}:{:0>2}.{:0>3}",
+        millis / 60000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+pub fn pieces(gs: &GameState) -> usize {
+    gs.statistics.values().sum()
+}
+
+pub fn pieces_per_second(gs: &GameState) -> f32 {
+    if gs.score.frames == 0 {
+        return 0.0;
+    }
+
+    pieces(gs) as f32 * 60.0 / gs.score.frames as f32
+}
+
+// heading and value pairs for the score panel
+pub fn hud(gs: &GameState) -> Vec<(String, String)> {
+    let labels = gs.scoring.labels();
+    match gs.options.game_type {
+        GameType::Sprint => vec![
+            (
+                "LINES".to_string(),
+                format!("{:0>3}", sprint_goal(gs).saturating_sub(gs.score.lines)),
+            ),
+            ("TIME".to_string(), format_ticks(gs.score.frames)),
+            ("PPS".to_string(), format!("{:.2}", pieces_per_second(gs))),
+        ],
+        GameType::Dig => vec![
+            ("GARBAGE".to_string(), format!("{:0>2}", garbage_left(gs))),
+            ("TIME".to_string(), format_ticks(gs.score.frames)),
+            ("PIECES".to_string(), format!("{:0>3}", pieces(gs))),
+        ],
+        GameType::Puzzle => {
+            let (goal, given) = match gs.puzzles.get(gs.options.puzzle) {
+                Some(puzzle) => (puzzle.objective.short_name(), puzzle.pieces()),
+                None => (String::new(), 0),
+            };
+            vec![
+                ("GOAL".to_string(), goal),
+                (
+                    "PIECES".to_string(),
+                    format!("{:0>2}", given.saturating_sub(pieces(gs))),
+                ),
+                ("LINES".to_string(), format!("{:0>3}", gs.score.lines)),
+            ]
+        }
+        GameType::Custom(_) => {
+            let (goal, end) = match custom_mode(gs) {
+                Some(mode) => (mode.goal, mode.end),
+                None => return Vec::new(),
+            };
+            let score = (
+                labels.score.to_string(),
+                format!("{:0>1$}", gs.score.val, labels.digits),
+            );
+            let lines = (labels.lines.to_string(), format!("{:0>3}", gs.score.lines));
+            let time = ("TIME".to_string(), format_ticks(gs.score.frames));
+            let progress = match goal {
+                Goal::Lines(goal) => (
+                    labels.lines.to_string(),
+                    format!("{:0>3}", goal.saturating_sub(gs.score.lines)),
+                ),
+                Goal::Time(frames) => (
+                    "TIME".to_string(),
+                    format_ticks(frames.saturating_sub(gs.score.frames)),
+                ),
+                Goal::Score(_) => score.clone(),
+                Goal::Garbage => ("GARBAGE".to_string(), format!("{:0>2}", garbage_left(gs))),
+            };
+            let limit = match end {
+                End::Time(frames) => (
+                    "TIME".to_string(),
+                    format_ticks(frames.saturating_sub(gs.score.frames)),
+                ),
+                End::Pieces(given) => (
+                    "PIECES".to_string(),
+                    format!("{:0>3}", given.saturating_sub(pieces(gs))),
+                ),
+                End::Topout if matches!(goal, Goal::Time(_)) => lines,
+                End::Topout => time,
+            };
+            let last = match goal {
+                Goal::Score(_) => (labels.level.to_string(), format!("{:0>2}", gs.score.level)),
+                _ => score,
+            };
+            vec![progress, limit, last]
+        }
+        GameType::Zen => vec![
+            ("LINES".to_string(), format!("{:0>3}", gs.score.lines)),
+            ("TIME".to_string(), format_ticks(gs.score.frames)),
+            ("PIECES".to_string(), format!("{:0>3}", pieces(gs))),
+        ],
+        GameType::Survival => vec![
+            ("TIME".to_string(), format_ticks(gs.score.frames)),
+            ("LINES".to_string(), format!("{:0>3}", gs.score.lines)),
+            (
+                "RISE".to_string(),
+                format_ticks(gs.garbage_interval.saturating_sub(gs.garbage_timer)),
+            ),
+        ],
+        GameType::Master => {
+            let next = if gs.score.level >= 900 {
+                MASTER_LEVEL
+            } else {
+                gs.score.level / 100 * 100 + 100
+            };
+            vec![
+                (
+                    labels.level.to_string(),
+                    format!("{:0>3}/{}", gs.score.level, next),
+                ),
+                ("GRADE".to_string(), master_grade(gs).to_string()),
+                ("TIME".to_string(), format_ticks(gs.score.frames)),
+            ]
+        }
+        GameType::Ultra => vec![
+            (
+                "TIME".to_string(),
+                format_ticks(ultra_frames(gs).saturating_sub(gs.score.frames)),
+            ),
+            (labels.lines.to_string(), format!("{:0>3}", gs.score.lines)),
+            (
+                labels.score.to_string(),
+                format!("{:0>1$}", gs.score.val, labels.digits),
+            ),
+        ],
+        GameType::Marathon | GameType::BType | GameType::Big => {
+            let lines = match gs.options.game_type {
+                GameType::BType => B_TYPE_LINES.saturating_sub(gs.score.lines),
+                _ => gs.score.lines,
+            };
+            vec![
+                (labels.lines.to_string(), format!("{:0>3}", lines)),
+                (labels.level.to_string(), format!("{:0>2}", gs.score.level)),
+                (
+                    labels.score.to_string(),
+                    format!("{:0>1$}", gs.score.val, labels.digits),
+                ),
+            ]
+        }
+    }
+}
+
+// lines of the results screen
+pub fn summary(gs: &GameState) -> Vec<String> {
+    let mut summary = vec![game_type_name(gs, gs.options.game_type)];
+    match gs.options.game_type {
+        GameType::Sprint | GameType::Dig => {
+            summary.push(format!("TIME {}", format_ticks(gs.score.frames)));
+            summary.push(format!("PIECES {}", pieces(gs)));
+            summary.push(format!("PPS {:.2}", pieces_per_second(gs)));
+            if gs.options.game_type == GameType::Sprint {
+                summary.push(format!("FINESSE {}", gs.score.finesse_faults));
+            }
+            summary.push(match gs.score.record {
+                Some(best) if best <= gs.score.frames => {
+                    format!(
+                        "BEST {} +{}",
+                        format_ticks(best),
+                        format_ticks(gs.score.frames - best)
+                    )
+                }
+                Some(best) => format!("NEW BEST -{}", format_ticks(best - gs.score.frames)),
+                None => "NEW BEST".to_string(),
+            });
+        }
+        GameType::Puzzle => {
+            if let Some(puzzle) = gs.puzzles.get(gs.options.puzzle) {
+                summary.push(puzzle.name.clone());
+                summary.push(puzzle.objective.name());
+            }
+            summary.push(format!("PIECES {}", pieces(gs)));
+        }
+        GameType::Custom(_) => {
+            if let Some(mode) = custom_mode(gs) {
+                summary.push(mode.goal.name());
+            }
+            summary.push(format!("SCORE {}", gs.score.val));
+            summary.push(format!("LINES {}", gs.score.lines));
+            summary.push(format!("TIME {}", format_ticks(gs.score.frames)));
+            summary.push(format!("PIECES {}", pieces(gs)));
+        }
+        GameType::Zen => {
+            summary.push(format!("LINES {}", gs.score.lines));
+            summary.push(format!("TIME {}", format_ticks(gs.score.frames)));
+            summary.push(format!("PIECES {}", pieces(gs)));
+        }
+        GameType::Survival => {
+            summary.push(format!("SURVIVED {}", format_ticks(gs.score.frames)));
+            summary.push(format!("LINES {}", gs.score.lines));
+            summary.push(format!("PIECES {}", pieces(gs)));
+        }
+        GameType::Master => {
+            summary.push(format!("GRADE {}", master_grade(gs)));
+            summary.push(format!("LEVEL {} POINTS {}", gs.score.level, gs.score.val));
+            summary.push(format!("TIME {}", format_ticks(gs.score.frames)));
+            // sections two to a line
+            for pair in section_times(gs).chunks(2) {
+                let times: Vec<String> = pair.iter().map(|t| format_ticks(*t)).collect();
+                summary.push(times.join(" "));
+            }
+        }
+        GameType::Ultra => {
+            summary.push(format!("SCORE {}", gs.score.val));
+            summary.push(format!("LINES {}", gs.score.lines));
+            summary.push(format!("PIECES {}", pieces(gs)));
+            summary.push(match gs.score.rank {
+                Some(0) => "NEW HIGH SCORE".to_string(),
+                Some(rank) => format!("RANK {}", rank + 1),
+                None => "NOT RANKED".to_string(),
+            });
+        }
+        GameType::Marathon | GameType::BType | GameType::Big => {
+            summary.push(format!("SCORE {}", gs.score.val));
+            summary.push(format!("LINES {}", gs.score.lines));
+            summary.push(format!("LEVEL {}", gs.score.level));
+            summary.push(format!("TIME {}", format_ticks(gs.score.frames)));
+            if gs.score.bonus > 0 {
+                summary.push(format!("BONUS {}", gs.score.bonus));
+            }
+        }
+    }
+
+    summary
+}
+
+// rows still holding garbage, lines waiting to be cleared do not count
+pub fn garbage_left(gs: &GameState) -> usize {
+    let cleared = match &gs.line_clear {
+        Some(line_clear) => line_clear.lines.clone(),
+        None => Vec::new(),
+    };
+    (0..WELL_HEIGHT)
+        .filter(|y| !cleared.contains(y))
+        .filter(|y| {
+            (0..WELL_WIDTH).any(|x| match gs.placed_blocks[y * WELL_WIDTH + x] {
+                Some(block) => block.garbage,
+                None => false,
+            })
+        })
+        .count()
+}
+
+fn sprint_goal(gs: &GameState) -> usize {
+    SPRINT_LINES[gs.options.sprint_goal]
+}
+
+fn ultra_frames(gs: &GameState) -> usize {
+    ULTRA_MINUTES[gs.options.ultra_time] * 60 * 60
+}
+
+pub fn high_score_key(gs: &GameState) -> String {
+    match gs.options.game_type {
+        GameType::Ultra => format!("ultra_{}", ULTRA_MINUTES[gs.options.ultra_time]),
+        _ => gs.options.game_type.name().to_lowercase(),
+    }
+}
+
+pub fn load_high_scores(key: &str) -> Vec<usize> {
+    match config::load(config::RECORDS_PATH) {
+        Some(records) => records
+            .get(key)
+            .unwrap_or("")
+            .split(',')
+            .filter_map(|score| score.trim().parse().ok())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+// inserts the score into the table and returns its rank
+fn store_high_score(key: &str, score: usize) -> Option<usize> {
+    let mut table = load_high_scores(key);
+    let rank = table.iter().position(|s| score > *s).unwrap_or(table.len());
+    if rank >= HIGH_SCORE_ENTRIES {
+        return None;
+    }
+    table.insert(rank, score);
+    table.truncate(HIGH_SCORE_ENTRIES);
+
+    let mut records = config::load(config::RECORDS_PATH).unwrap_or_default();
+    let values: Vec<String> = table.iter().map(|s| s.to_string()).collect();
+    records.set(key, values.join(", "));
+    config::save(config::RECORDS_PATH, &records.serialize());
+
+    Some(rank)
+}
+
+fn finish_ultra(gs: &mut GameState) {
+    let key = high_score_key(gs);
+    gs.score.rank = store_high_score(&key, gs.score.val);
+    gs.high_scores = load_high_scores(&key);
+    gs.game_mode = GameMode::Ending;
+}
+
+pub fn ending_heading(gs: &GameState) -> &'static str {
+    if gs.score.topout {
+        return "GAME OVER";
+    }
+    match gs.options.game_type {
+        GameType::Ultra => "TIME UP",
+        GameType::Zen => "SESSION OVER",
+        GameType::Puzzle if gs.score.solved => "SOLVED",
+        GameType::Puzzle => "FAILED",
+        GameType::Custom(_) if gs.score.solved => "GOAL REACHED",
+        GameType::Custom(_) => "FAILED",
+        _ => "CONGRATULATIONS",
+    }
+}
+
+// a marathon that reached its goal may go on without one
+pub fn can_continue(gs: &GameState) -> bool {
+    gs.options.game_type == GameType::Marathon && !gs.score.topout && !gs.endless
+}
+
+// keeps the lower value and returns the one stored before
+fn store_best_time(key: &str, frames: usize) -> Option<usize> {
+    let mut records = config::load(config::RECORDS_PATH).unwrap_or_default();
+    let best = records.get(key).and_then(|v| v.parse().ok());
+    match best {
+        Some(best) if best <= frames => {}
+        _ => {
+            records.set(key, frames.to_string());
+            config::save(config::RECORDS_PATH, &records.serialize());
+        }
+    }
+
+    best
+}
+
+// checked after every lock
+pub fn check_goal(gs: &mut GameState) {
+    match gs.options.game_type {
+        GameType::BType => {
+            if gs.score.lines >= B_TYPE_LINES {
+                gs.score.bonus = 1000 * (gs.options.start_level + gs.options.height);
+                gs.score.val += gs.score.bonus;
+                gs.game_mode = GameMode::Ending;
+            }
+        }
+        GameType::Sprint => {
+            let goal = sprint_goal(gs);
+            if gs.score.lines >= goal {
+                let key = format!("sprint_{}", goal);
+                gs.score.record = store_best_time(&key, gs.score.frames);
+                gs.game_mode = GameMode::Ending;
+            }
+        }
+        GameType::Marathon => {
+            let reached = match MARATHON_GOALS[gs.options.marathon_goal] {
+                MarathonGoal::Lines(lines) => gs.score.lines >= lines,
+                MarathonGoal::Level(level) => gs.score.level >= level,
+                MarathonGoal::Endless => false,
+            };
+            if reached && !gs.endless {
+                gs.menu_cursor = 0;
+                gs.game_mode = GameMode::Ending;
+            }
+        }
+        GameType::Dig => {
+            if garbage_left(gs) == 0 {
+                let key = format!("dig_{}", DIG_ROWS[gs.options.dig_rows]);
+                gs.score.record = store_best_time(&key, gs.score.frames);
+                gs.game_mode = GameMode::Ending;
+            }
+        }
+        GameType::Master => {
+            if gs.score.level >= MASTER_LEVEL {
+                gs.game_mode = GameMode::Ending;
+            }
+        }
+        GameType::Custom(_) => {
+            let (goal, end) = match custom_mode(gs) {
+                Some(mode) => (mode.goal, mode.end),
+                None => return,
+            };
+            gs.score.solved = match goal {
+                Goal::Lines(lines) => gs.score.lines >= lines,
+                Goal::Score(score) => gs.score.val >= score,
+                Goal::Garbage => garbage_left(gs) == 0,
+                // checked every frame
+                Goal::Time(_) => false,
+            };
+            let out_of_pieces = matches!(end, End::Pieces(given) if pieces(gs) >= given);
+            if gs.score.solved || out_of_pieces {
+                gs.game_mode = GameMode::Ending;
+            }
+        }
+        GameType::Ultra | GameType::Survival | GameType::Zen | GameType::Puzzle | GameType::Big => {
+        }
+    }
+}
@@ -0,0 +1,177 @@
+/*
+ Puzzles
+ Missions read from a text file. A puzzle starts with its name in brackets,
+ followed by `key = value` lines. Board rows are listed top to bottom and sit
+ on the floor of the well, `.` is empty, `X` is garbage and a piece letter is
+ a block of that piece.
+
+ [TETRIS PRACTICE]
+ goal = lines 4
+ queue = I
+ hold = no
+ row = XXXXXXXXX.
+*/
+use super::{
+    components::{Block, TetrominoType, LIGHT, WELL_HEIGHT, WELL_WIDTH},
+    xy_idx,
+};
+
+pub const PUZZLES_PATH: &str = "assets/puzzles.txt";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Objective {
+    Lines(usize),
+    TSpinTriple,
+    PerfectClear,
+    NoHoles,
+}
+
+impl Objective {
+    pub fn name(self) -> String {
+        match self {
+            Objective::Lines(lines) => format!("CLEAR {} LINES", lines),
+            Objective::TSpinTriple => "T-SPIN TRIPLE".to_string(),
+            Objective::PerfectClear => "PERFECT CLEAR".to_string(),
+            Objective::NoHoles => "NO HOLES".to_string(),
+        }
+    }
+
+    // fits the score panel
+    pub fn short_name(self) -> String {
+        match self {
+            Objective::Lines(lines) => format!("{} LINES", lines),
+            Objective::TSpinTriple => "TST".to_string(),
+            Objective::PerfectClear => "PC".to_string(),
+            Objective::NoHoles => "NO HOLE".to_string(),
+        }
+    }
+
+    fn parse(text: &str) -> Option<Objective> {
+        let text = text.to_lowercase();
+        match text.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["lines", lines] => lines.parse().ok().map(Objective::Lines),
+            ["tst"] | ["t-spin", "triple"] => Some(Objective::TSpinTriple),
+            ["pc"] | ["perfect", "clear"] => Some(Objective::PerfectClear),
+            ["no", "holes"] => Some(Objective::NoHoles),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Puzzle {
+    pub name: String,
+    pub objective: Objective,
+    pub queue: Vec<TetrominoType>,
+    pub hold_allowed: bool,
+    // a piece already waiting in hold
+    pub held: Option<TetrominoType>,
+    pub rows: Vec<String>,
+    pub cleared: bool,
+}
+
+impl Puzzle {
+    // every piece the player gets, including one in hold
+    pub fn pieces(&self) -> usize {
+        self.queue.len() + self.held.map_or(0, |_| 1)
+    }
+
+    pub fn board(&self) -> Vec<Option<Block>> {
+        let mut placed = vec![None; WELL_WIDTH * WELL_HEIGHT];
+        let top = WELL_HEIGHT.saturating_sub(self.rows.len());
+        for (i, row) in self.rows.iter().rev().take(WELL_HEIGHT).rev().enumerate() {
+            for (x, c) in row.chars().take(WELL_WIDTH).enumerate() {
+                let block = match c {
+                    'X' | 'x' => Some(Block {
+                        color: LIGHT,
+                        kind: TetrominoType::O,
+                        garbage: true,
+                        locked: 0,
+                        piece: 0,
+                    }),
+                    c => piece_kind(c).map(|kind| Block {
+                        color: LIGHT,
+                        kind,
+                        garbage: false,
+                        locked: 0,
+                        piece: 0,
+                    }),
+                };
+                placed[xy_idx(x as f32, (top + i) as f32)] = block;
+            }
+        }
+
+        placed
+    }
+}
+
+fn piece_kind(c: char) -> Option<TetrominoType> {
+    match c.to_ascii_uppercase() {
+        'I' => Some(TetrominoType::I),
+        'J' => Some(TetrominoType::J),
+        'L' => Some(TetrominoType::L),
+        'O' => Some(TetrominoType::O),
+        'S' => Some(TetrominoType::S),
+        'T' => Some(TetrominoType::T),
+        'Z' => Some(TetrominoType::Z),
+        _ => None,
+    }
+}
+
+// puzzles without a goal or a queue are left out
+pub fn parse(text: &str) -> Vec<Puzzle> {
+    let mut puzzles = Vec::new();
+    let mut current: Option<(Puzzle, bool)> = None;
+    for line in text.lines() {
+        let line = match line.find('#') {
+            Some(idx) => &line[..idx],
+            None => line,
+        };
+        let line = line.trim();
+        if line.starts_with('[') && line.ends_with(']') {
+            puzzles.extend(
+                current
+                    .take()
+                    .filter(|(p, goal)| *goal && !p.queue.is_empty()),
+            );
+            let puzzle = Puzzle {
+                name: line[1..line.len() - 1].trim().to_uppercase(),
+                objective: Objective::NoHoles,
+                queue: Vec::new(),
+                hold_allowed: true,
+                held: None,
+                rows: Vec::new(),
+                cleared: false,
+            };
+            current = Some((puzzle, false));
+            continue;
+        }
+
+        let (puzzle, goal) = match &mut current {
+            Some((puzzle, goal)) => (puzzle, goal),
+            None => continue,
+        };
+        if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "goal" => {
+                    if let Some(objective) = Objective::parse(value) {
+                        puzzle.objective = objective;
+                        *goal = true;
+                    }
+                }
+                "queue" => puzzle.queue = value.chars().filter_map(piece_kind).collect(),
+                "hold" => match value.to_lowercase().as_str() {
+                    "no" => puzzle.hold_allowed = false,
+                    "yes" => puzzle.hold_allowed = true,
+                    _ => puzzle.held = value.chars().find_map(piece_kind),
+                },
+                "row" => puzzle.rows.push(value.to_string()),
+                _ => {}
+            }
+        }
+    }
+    puzzles.extend(current.filter(|(p, goal)| *goal && !p.queue.is_empty()));
+
+    puzzles.into_iter().map(|(p, _)| p).collect()
+}
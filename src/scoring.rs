@@ -0,0 +1,217 @@
+/*
+ Scoring systems
+ Every lock is reported as a LockEvent and the active system decides what it is worth.
+*/
+use super::components::SpinType;
+
+pub struct LockEvent {
+    pub lines: usize,
+    pub spin: SpinType,
+    // number of line clearing locks directly before this one
+    pub combo: usize,
+    // this clear continues a back-to-back chain of tetrises and spins
+    pub b2b: bool,
+    pub perfect_clear: bool,
+    pub soft_drop: usize,
+    pub hard_drop: usize,
+    // level before the lines of this lock are counted
+    pub level: usize,
+}
+
+impl LockEvent {
+    pub fn is_difficult(&self) -> bool {
+        self.lines == 4 || (self.lines > 0 && self.spin != SpinType::None)
+    }
+}
+
+// what a player would call the lock, e.g. "T-SPIN DOUBLE"
+pub fn callout_names(event: &LockEvent) -> Vec<String> {
+    let mut names = Vec::new();
+    let clear = match event.lines {
+        1 => "SINGLE",
+        2 => "DOUBLE",
+        3 => "TRIPLE",
+        4 => "TETRIS",
+        _ => "",
+    };
+    let name = match event.spin {
+        SpinType::None => clear.to_string(),
+        SpinType::Mini => format!("T-SPIN MINI {}", clear),
+        SpinType::Full => format!("T-SPIN {}", clear),
+    };
+    if !name.is_empty() {
+        names.push(name.trim_end().to_string());
+    }
+    if event.b2b {
+        names.push("B2B".to_string());
+    }
+    if event.lines > 0 && event.combo > 0 {
+        names.push(format!("COMBO X{}", event.combo));
+    }
+    if event.perfect_clear {
+        names.push("PERFECT CLEAR".to_string());
+    }
+
+    names
+}
+
+pub struct ScoreLabels {
+    pub lines: &'static str,
+    pub level: &'static str,
+    pub score: &'static str,
+    pub digits: usize,
+}
+
+pub trait ScoringSystem {
+    fn name(&self) -> &'static str;
+    fn labels(&self) -> ScoreLabels;
+    fn score(&mut self, event: &LockEvent) -> usize;
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ScoringKind {
+    Nes,
+    Guideline,
+    Tgm,
+}
+
+impl ScoringKind {
+    pub fn next(self) -> ScoringKind {
+        match self {
+            ScoringKind::Nes => ScoringKind::Guideline,
+            ScoringKind::Guideline => ScoringKind::Tgm,
+            ScoringKind::Tgm => ScoringKind::Nes,
+        }
+    }
+
+    pub fn prev(self) -> ScoringKind {
+        self.next().next()
+    }
+
+    pub fn system(self) -> Box<dyn ScoringSystem> {
+        match self {
+            ScoringKind::Nes => Box::new(NesScoring),
+            ScoringKind::Guideline => Box::new(GuidelineScoring),
+            ScoringKind::Tgm => Box::new(TgmScoring { combo: 1 }),
+        }
+    }
+}
+
+// Nintendo Entertainment System, 1989
+pub struct NesScoring;
+
+impl ScoringSystem for NesScoring {
+    fn name(&self) -> &'static str {
+        "NES"
+    }
+
+    fn labels(&self) -> ScoreLabels {
+        ScoreLabels {
+            lines: "LINES",
+            level: "LEVEL",
+            score: "SCORE",
+            digits: 6,
+        }
+    }
+
+    fn score(&mut self, event: &LockEvent) -> usize {
+        let base = match event.lines {
+            1 => 40,
+            2 => 100,
+            3 => 300,
+            4 => 1200,
+            _ => 0,
+        };
+
+        // the NES only rewards rows that were pushed down
+        base * (event.level + 1) + event.soft_drop
+    }
+}
+
+// Tetris Guideline, levels are counted from 1
+pub struct GuidelineScoring;
+
+impl ScoringSystem for GuidelineScoring {
+    fn name(&self) -> &'static str {
+        "GUIDELINE"
+    }
+
+    fn labels(&self) -> ScoreLabels {
+        ScoreLabels {
+            lines: "LINES",
+            level: "LEVEL",
+            score: "SCORE",
+            digits: 7,
+        }
+    }
+
+    fn score(&mut self, event: &LockEvent) -> usize {
+        let level = event.level + 1;
+        let base = match (event.spin, event.lines) {
+            (SpinType::None, 1) => 100,
+            (SpinType::None, 2) => 300,
+            (SpinType::None, 3) => 500,
+            (SpinType::None, 4) => 800,
+            (SpinType::Mini, 0) => 100,
+            (SpinType::Mini, 1) => 200,
+            (SpinType::Mini, _) => 400,
+            (SpinType::Full, 0) => 400,
+            (SpinType::Full, 1) => 800,
+            (SpinType::Full, 2) => 1200,
+            (SpinType::Full, _) => 1600,
+            _ => 0,
+        };
+
+        let mut score = base * level;
+        if event.b2b {
+            score = score * 3 / 2;
+        }
+        if event.lines > 0 {
+            score += 50 * event.combo * level;
+        }
+        if event.perfect_clear {
+            score += match event.lines {
+                1 => 800,
+                2 => 1200,
+                3 => 1800,
+                _ if event.b2b => 3200,
+                _ => 2000,
+            } * level;
+        }
+
+        score + event.soft_drop + event.hard_drop * 2
+    }
+}
+
+// Tetris The Grand Master
+pub struct TgmScoring {
+    combo: usize,
+}
+
+impl ScoringSystem for TgmScoring {
+    fn name(&self) -> &'static str {
+        "TGM"
+    }
+
+    fn labels(&self) -> ScoreLabels {
+        ScoreLabels {
+            lines: "LINES",
+            level: "LEVEL",
+            score: "POINTS",
+            digits: 6,
+        }
+    }
+
+    fn score(&mut self, event: &LockEvent) -> usize {
+        if event.lines == 0 {
+            self.combo = 1;
+            return 0;
+        }
+
+        self.combo += 2 * event.lines - 2;
+        let bravo = if event.perfect_clear { 4 } else { 1 };
+        let speed = (event.level + event.lines).div_ceil(4) + event.soft_drop + 2 * event.hard_drop;
+
+        speed * event.lines * self.combo * bravo
+    }
+}
@@ -8,7 +8,7 @@ use macroquad::{
     rand,
 };
 
-use crate::components::{GameState, Gravity, WELL_WIDTH};
+use crate::components::{GameState, GameType, Gravity, Randomizer, LIGHT, WELL_WIDTH};
 
 use super::{xy_idx, Block, Tetromino, TetrominoType, WELL_HEIGHT};
 
@@ -77,16 +77,27 @@ fn special_mats() -> Vec<(TetrominoType, Mat4)> {
     vec![(TetrominoType::I, I), (TetrominoType::O, O)]
 }
 
+fn spawn_pos(width: i32, scale: usize) -> Vec2 {
+    vec2(
+        f32::floor((WELL_WIDTH / scale) as f32 / 2.0 - width as f32 / 2.0),
+        (WELL_HEIGHT / scale) as f32 - 2.0,
+    )
+}
+
+// big pieces move on a grid of scale x scale cells
+pub fn set_scale(tetromino: &mut Tetromino, scale: usize) {
+    tetromino.scale = scale;
+    tetromino.pos = spawn_pos(tetromino.width, scale);
+    tetromino.spawn_pos = tetromino.pos;
+}
+
 pub fn tetromino_set() -> Vec<Tetromino> {
     let mut tetrominos = Vec::new();
     let mats = basic_mats();
     let mats2 = special_mats();
     for (t, mat) in mats.iter() {
         let width = 3;
-        let pos = vec2(
-            f32::floor(5.0 - width as f32 / 2.0),
-            f32::floor(WELL_HEIGHT as f32) - 2.0,
-        );
+        let pos = spawn_pos(width, 1);
         let (color, ghost_color) = tetromino_color(t);
         tetrominos.push(Tetromino {
             pos,
@@ -103,14 +114,15 @@ pub fn tetromino_set() -> Vec<Tetromino> {
             locking: false,
             sonic_lock: false,
             held: false,
+            soft_dropped: 0,
+            hard_dropped: 0,
+            inputs: 0,
+            scale: 1,
         });
     }
     for (t, mat) in mats2.iter() {
         let width = 4;
-        let pos = vec2(
-            f32::floor(5.0 - width as f32 / 2.0),
-            f32::floor(WELL_HEIGHT as f32) - 2.0,
-        );
+        let pos = spawn_pos(width, 1);
         let (color, ghost_color) = tetromino_color(t);
         tetrominos.push(Tetromino {
             pos,
@@ -127,6 +139,10 @@ pub fn tetromino_set() -> Vec<Tetromino> {
             locking: false,
             sonic_lock: false,
             held: false,
+            soft_dropped: 0,
+            hard_dropped: 0,
+            inputs: 0,
+            scale: 1,
         });
     }
 
@@ -163,20 +179,119 @@ pub fn despawn_blocks(placed: &mut Vec<Option<Block>>, lines: &Vec<usize>) {
     }
 }
 
+pub fn random_kind() -> TetrominoType {
+    let kinds = [
+        TetrominoType::J,
+        TetrominoType::L,
+        TetrominoType::S,
+        TetrominoType::T,
+        TetrominoType::Z,
+        TetrominoType::I,
+        TetrominoType::O,
+    ];
+    kinds[rand::gen_range(0, kinds.len())]
+}
+
+// random rows at the bottom of the well, every row keeps at least one hole
+pub fn fill_garbage(placed: &mut [Option<Block>], height: usize) {
+    for y in (WELL_HEIGHT - height)..WELL_HEIGHT {
+        let hole = rand::gen_range(0, WELL_WIDTH);
+        for x in 0..WELL_WIDTH {
+            if x == hole || rand::gen_range(0, 2) == 0 {
+                continue;
+            }
+            placed[xy_idx(x as f32, y as f32)] = Some(Block {
+                color: LIGHT,
+                kind: random_kind(),
+                garbage: true,
+                locked: 0,
+                piece: 0,
+            });
+        }
+    }
+}
+
+// full rows with a single hole each, clean rows mostly keep the hole of the row below
+pub fn fill_cheese(placed: &mut [Option<Block>], height: usize, messy: bool) {
+    let mut hole = rand::gen_range(0, WELL_WIDTH);
+    for y in ((WELL_HEIGHT - height)..WELL_HEIGHT).rev() {
+        if messy || rand::gen_range(0, 10) < 3 {
+            hole = rand::gen_range(0, WELL_WIDTH);
+        }
+        for x in 0..WELL_WIDTH {
+            if x == hole {
+                continue;
+            }
+            placed[xy_idx(x as f32, y as f32)] = Some(Block {
+                color: LIGHT,
+                kind: random_kind(),
+                garbage: true,
+                locked: 0,
+                piece: 0,
+            });
+        }
+    }
+}
+
+// pushes the stack up by one row over a new garbage row, true if blocks left the top
+pub fn push_garbage_row(placed: &mut [Option<Block>], frame: usize) -> bool {
+    let overflow = (0..WELL_WIDTH).any(|x| placed[x].is_some());
+    placed.copy_within(WELL_WIDTH.., 0);
+
+    let hole = rand::gen_range(0, WELL_WIDTH);
+    for x in 0..WELL_WIDTH {
+        placed[xy_idx(x as f32, (WELL_HEIGHT - 1) as f32)] = if x == hole {
+            None
+        } else {
+            Some(Block {
+                color: LIGHT,
+                kind: random_kind(),
+                garbage: true,
+                locked: frame,
+                piece: 0,
+            })
+        };
+    }
+
+    overflow
+}
+
+pub fn next_tetromino(gs: &mut GameState) -> Tetromino {
+    match gs.randomizer {
+        Randomizer::Random => spawn_tetromino(&gs.tetrominos),
+        Randomizer::Bag => {
+            if gs.bag.is_empty() {
+                gs.bag = gs.tetrominos.clone();
+            }
+            gs.bag.remove(rand::gen_range(0, gs.bag.len()))
+        }
+    }
+}
+
 pub fn drain_next(gs: &mut GameState) -> Tetromino {
     let tetromino = gs.next.drain(0..1).collect::<Vec<Tetromino>>()[0];
-    gs.next.push(spawn_tetromino(&gs.tetrominos));
+    // puzzles play a fixed queue
+    if gs.options.game_type != GameType::Puzzle {
+        let tetromino = next_tetromino(gs);
+        gs.next.push(tetromino);
+    }
     gs.ghost.dirty = true;
     gs.gravity.meter = 0.0;
+    gs.spawns += 1;
 
     tetromino
 }
 
 pub fn reset_transform(tetromino: &mut Tetromino) {
+    let width = (WELL_WIDTH / tetromino.scale) as f32;
+    let height = (WELL_HEIGHT / tetromino.scale) as f32;
     let pos = vec2(
-        f32::floor(5.0 - tetromino.width as f32 / 2.0),
-        f32::floor(WELL_HEIGHT as f32 - tetromino.width as f32 / 2.0) + 1.0,
+        f32::floor(width / 2.0 - tetromino.width as f32 / 2.0),
+        f32::floor(height - tetromino.width as f32 / 2.0) + 1.0,
     );
+    tetromino.soft_dropped = 0;
+    tetromino.hard_dropped = 0;
+    tetromino.inputs = 0;
     match tetromino.kind {
         TetrominoType::I | TetrominoType::O => {
             let mats = special_mats();